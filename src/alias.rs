@@ -0,0 +1,559 @@
+//! Tool aliasing for renamed and deprecated tools.
+//!
+//! [`AliasedTools`] wraps a tools provider and accepts old tool names in `call_tool`,
+//! forwarding them to the renamed tool. Uses of an alias can be reported to the client
+//! as a log notification or in the result metadata.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use rmcp::{
+    model::{
+        CallToolRequestParams, CallToolResult, ErrorData, ListToolsResult, LoggingLevel,
+        LoggingMessageNotificationParam, Meta, PaginatedRequestParams, Tool,
+    },
+    service::{RequestContext, RoleServer},
+};
+use serde_json::json;
+
+use crate::pagination::fetch_all;
+use crate::providers::ToolsProvider;
+
+/// Logger name used for deprecation log notifications.
+const LOGGER: &str = "rmcp-server-builder";
+
+/// Key of the deprecation entry added to result metadata.
+pub const DEPRECATION_META_KEY: &str = "deprecated";
+
+/// Mapping from old tool names to their replacements.
+///
+/// # Example
+///
+/// ```
+/// use rmcp_server_builder::ToolAliases;
+///
+/// let aliases = ToolAliases::new()
+///     .alias("search_issues", "issues_search")
+///     .hide_aliases()
+///     .log_deprecations();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ToolAliases {
+    aliases: BTreeMap<String, String>,
+    hidden: bool,
+    log: bool,
+    meta: bool,
+}
+
+impl ToolAliases {
+    /// Create an empty set of aliases.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `alias` as an old name of the tool `target`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alias` is the name of a tool another alias refers to, or if `target`
+    /// is itself an alias, since either would hide a real tool behind an alias.
+    pub fn alias(mut self, alias: impl Into<String>, target: impl Into<String>) -> Self {
+        let (alias, target) = (alias.into(), target.into());
+        assert!(
+            alias != target && !self.aliases.values().any(|t| *t == alias),
+            "alias `{alias}` collides with a tool name"
+        );
+        assert!(
+            !self.aliases.contains_key(&target),
+            "alias target `{target}` is itself an alias"
+        );
+        self.aliases.insert(alias, target);
+        self
+    }
+
+    /// Do not list aliases in `list_tools`; they remain callable.
+    ///
+    /// By default each alias is listed as a copy of its target tool whose description
+    /// points to the new name.
+    pub fn hide_aliases(mut self) -> Self {
+        self.hidden = true;
+        self
+    }
+
+    /// Send a warning log notification to the client when an alias is called.
+    pub fn log_deprecations(mut self) -> Self {
+        self.log = true;
+        self
+    }
+
+    /// Add a [`DEPRECATION_META_KEY`] entry to the result metadata when an alias is called.
+    pub fn mark_deprecations(mut self) -> Self {
+        self.meta = true;
+        self
+    }
+
+    /// Get the tool an alias refers to.
+    pub fn resolve(&self, name: &str) -> Option<&str> {
+        self.aliases.get(name).map(String::as_str)
+    }
+
+    fn is_target(&self, name: &str) -> bool {
+        self.aliases.values().any(|target| target == name)
+    }
+
+    /// Build the listed copy of the tool `target` under the name `alias`.
+    fn deprecated(alias: &str, target: &Tool) -> Tool {
+        let mut tool = target.clone();
+        tool.name = alias.to_owned().into();
+        let notice = format!("Deprecated: use `{}` instead.", target.name);
+        tool.description = Some(match &tool.description {
+            Some(description) => format!("{notice} {description}").into(),
+            None => notice.into(),
+        });
+        tool
+    }
+}
+
+/// The aliases of a server, from the innermost [`AliasedTools`] out.
+///
+/// Servers attach their chain to request contexts, so adapters keying state on tool
+/// names can count calls through an alias as calls of the tool it refers to.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct AliasChain(Arc<[ToolAliases]>);
+
+impl AliasChain {
+    /// Get the name of a called tool, resolving aliases from the outermost in.
+    pub(crate) fn resolve<'a>(&'a self, name: &'a str) -> &'a str {
+        self.0
+            .iter()
+            .rev()
+            .fold(name, |name, aliases| aliases.resolve(name).unwrap_or(name))
+    }
+
+    /// Get the name of a tool called as `name` in a request to a server.
+    pub(crate) fn canonical_name<'a>(
+        context: &'a RequestContext<RoleServer>,
+        name: &'a str,
+    ) -> &'a str {
+        match context.extensions.get::<Self>() {
+            Some(chain) => chain.resolve(name),
+            None => name,
+        }
+    }
+}
+
+impl From<Vec<ToolAliases>> for AliasChain {
+    fn from(aliases: Vec<ToolAliases>) -> Self {
+        Self(aliases.into())
+    }
+}
+
+/// Tools provider accepting old tool names configured in [`ToolAliases`].
+///
+/// Listed aliases are appended to the last page of the list, so they appear whichever
+/// page their target is on. The targets' definitions are collected from the pages of a
+/// listing; the full list is fetched again only when the listing did not start at the
+/// first page. An alias whose target is not listed is left out of the list, and an
+/// alias named like a listed tool is ignored so the tool stays callable. Both are
+/// reported once as a warning with the `tracing` feature.
+///
+/// Usually configured through [`ServerBuilder::alias_tools`](crate::ServerBuilder::alias_tools).
+#[derive(Clone, Debug)]
+pub struct AliasedTools<T> {
+    inner: T,
+    aliases: ToolAliases,
+    listing: Arc<Mutex<Listing>>,
+}
+
+/// What the aliases have learned from the pages of the inner provider's list.
+#[derive(Debug, Default)]
+struct Listing {
+    /// Whether the pages recorded since the list was last fetched from its start
+    /// include the first one.
+    from_start: bool,
+    /// Definitions of the alias targets listed since then.
+    targets: HashMap<String, Tool>,
+    /// Targets a complete listing did not include.
+    missing: HashSet<String>,
+    /// Aliases named like a listed tool.
+    collisions: HashSet<String>,
+}
+
+impl<T> AliasedTools<T> {
+    /// Wrap a tools provider with aliases.
+    pub fn new(inner: T, aliases: ToolAliases) -> Self {
+        Self {
+            inner,
+            aliases,
+            listing: Arc::default(),
+        }
+    }
+
+    /// Get a reference to the wrapped provider.
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Record the definitions of alias targets and the aliases colliding with tools
+    /// listed on a page.
+    fn record(&self, restart: bool, tools: &[Tool]) {
+        let mut listing = self.listing.lock().unwrap();
+        if restart {
+            listing.from_start = true;
+            listing.targets.clear();
+        }
+        for tool in tools {
+            if self.aliases.is_target(&tool.name) {
+                listing.missing.remove(tool.name.as_ref());
+                listing.targets.insert(tool.name.to_string(), tool.clone());
+            }
+            if self.aliases.resolve(&tool.name).is_some()
+                && listing.collisions.insert(tool.name.to_string())
+            {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(
+                    alias = %tool.name,
+                    "alias collides with a tool of the same name and is ignored"
+                );
+            }
+        }
+    }
+
+    /// Alias targets neither listed nor known to be missing since the listing started.
+    fn unresolved(&self) -> bool {
+        let listing = self.listing.lock().unwrap();
+        self.aliases.aliases.values().any(|target| {
+            !listing.targets.contains_key(target) && !listing.missing.contains(target)
+        })
+    }
+
+    /// Append the listed copies of the aliases whose target is listed.
+    fn append(&self, tools: &mut Vec<Tool>) {
+        let mut listing = self.listing.lock().unwrap();
+        let Listing {
+            targets,
+            missing,
+            collisions,
+            ..
+        } = &mut *listing;
+        for (alias, target) in &self.aliases.aliases {
+            if collisions.contains(alias) {
+                continue;
+            }
+            match targets.get(target) {
+                Some(tool) => tools.push(ToolAliases::deprecated(alias, tool)),
+                None => {
+                    if missing.insert(target.clone()) {
+                        #[cfg(feature = "tracing")]
+                        tracing::warn!(
+                            %alias,
+                            %target,
+                            "alias target is not listed and the alias is hidden"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Get the tool a called name refers to, unless a listed tool has that name.
+    fn resolve(&self, name: &str) -> Option<&str> {
+        let target = self.aliases.resolve(name)?;
+        let collides = self.listing.lock().unwrap().collisions.contains(name);
+        (!collides).then_some(target)
+    }
+}
+
+impl<T: ToolsProvider> ToolsProvider for AliasedTools<T> {
+    async fn list_tools(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        let restart = request.as_ref().is_none_or(|r| r.cursor.is_none());
+        let meta = request.as_ref().and_then(|r| r.meta.clone());
+        let mut result = self.inner.list_tools(request, context.clone()).await?;
+        self.record(restart, &result.tools);
+        if self.aliases.hidden || result.next_cursor.is_some() {
+            return Ok(result);
+        }
+
+        let from_start = self.listing.lock().unwrap().from_start;
+        if !from_start && self.unresolved() {
+            let tools = fetch_all(meta, |params| {
+                let context = context.clone();
+                async move {
+                    let result = self.inner.list_tools(Some(params), context).await?;
+                    Ok((result.tools, result.next_cursor))
+                }
+            })
+            .await?;
+            self.record(true, &tools);
+        }
+        self.append(&mut result.tools);
+        Ok(result)
+    }
+
+    async fn call_tool(
+        &self,
+        mut request: CallToolRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let Some(target) = self.resolve(&request.name) else {
+            return self.inner.call_tool(request, context).await;
+        };
+        let alias = std::mem::replace(&mut request.name, target.to_owned().into());
+        let peer = context.peer.clone();
+        let mut result = self.inner.call_tool(request, context).await?;
+
+        let notice = json!({ "alias": alias, "replacement": target });
+        if self.aliases.meta {
+            result
+                .meta
+                .get_or_insert_with(Meta::new)
+                .insert(DEPRECATION_META_KEY.into(), notice.clone());
+        }
+        if self.aliases.log {
+            // A failed notification must not fail the call itself.
+            let _ = peer
+                .notify_logging_message(LoggingMessageNotificationParam {
+                    level: LoggingLevel::Warning,
+                    logger: Some(LOGGER.into()),
+                    data: json!({
+                        "message": format!("tool `{alias}` is deprecated, use `{target}` instead"),
+                        DEPRECATION_META_KEY: notice,
+                    }),
+                })
+                .await;
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deprecated_copy() {
+        let tool = Tool::new("issues_search", "Search issues.", Arc::default());
+        let alias = ToolAliases::deprecated("search_issues", &tool);
+        assert_eq!(alias.name, "search_issues");
+        assert_eq!(
+            alias.description.as_deref(),
+            Some("Deprecated: use `issues_search` instead. Search issues.")
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "collides with a tool name")]
+    fn test_rejects_alias_named_like_a_target() {
+        let _ = ToolAliases::new().alias("old", "new").alias("new", "other");
+    }
+
+    #[test]
+    #[should_panic(expected = "is itself an alias")]
+    fn test_rejects_alias_chains() {
+        let _ = ToolAliases::new().alias("old", "new").alias("older", "old");
+    }
+
+    mod session {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        use rmcp::model::{Content, Implementation};
+        use rmcp::service::{RoleClient, RunningService};
+
+        use super::*;
+        use crate::ServerBuilder;
+        use crate::test_support::{self, call};
+
+        /// Tools provider serving `names` one per page.
+        #[derive(Default)]
+        struct Catalogue {
+            names: &'static [&'static str],
+            pages: Arc<AtomicUsize>,
+        }
+
+        impl ToolsProvider for Catalogue {
+            async fn list_tools(
+                &self,
+                request: Option<PaginatedRequestParams>,
+                _context: RequestContext<RoleServer>,
+            ) -> Result<ListToolsResult, ErrorData> {
+                self.pages.fetch_add(1, Ordering::Relaxed);
+                let index: usize = request
+                    .and_then(|r| r.cursor)
+                    .map_or(0, |cursor| cursor.parse().unwrap());
+                Ok(ListToolsResult {
+                    meta: None,
+                    next_cursor: (index + 1 < self.names.len()).then(|| (index + 1).to_string()),
+                    tools: vec![Tool::new(self.names[index], "", Arc::default())],
+                })
+            }
+
+            async fn call_tool(
+                &self,
+                request: CallToolRequestParams,
+                _context: RequestContext<RoleServer>,
+            ) -> Result<CallToolResult, ErrorData> {
+                match self.names.contains(&request.name.as_ref()) {
+                    true => Ok(CallToolResult::success(vec![Content::text(
+                        request.name.to_string(),
+                    )])),
+                    false => Err(ErrorData::invalid_params("tool not found", None)),
+                }
+            }
+        }
+
+        async fn connect(
+            names: &'static [&'static str],
+            aliases: ToolAliases,
+        ) -> RunningService<RoleClient, ()> {
+            serve(
+                Catalogue {
+                    names,
+                    ..Default::default()
+                },
+                aliases,
+            )
+            .await
+        }
+
+        async fn serve(tools: Catalogue, aliases: ToolAliases) -> RunningService<RoleClient, ()> {
+            let server = ServerBuilder::new()
+                .info(Implementation::default())
+                .tools(tools)
+                .alias_tools(aliases)
+                .build();
+            test_support::connect(server, ()).await
+        }
+
+        #[tokio::test]
+        async fn test_lists_aliases_on_last_page() {
+            let client = connect(
+                &["issues_search", "issues_list"],
+                ToolAliases::new().alias("search_issues", "issues_search"),
+            )
+            .await;
+
+            let first = client.list_tools(None).await.unwrap();
+            assert_eq!(first.tools.len(), 1);
+            let names: Vec<_> = client
+                .list_all_tools()
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|tool| tool.name.to_string())
+                .collect();
+            assert_eq!(names, ["issues_search", "issues_list", "search_issues"]);
+
+            // A listing resumed on the last page still resolves the alias.
+            let last = client
+                .list_tools(Some(PaginatedRequestParams {
+                    meta: None,
+                    cursor: Some("1".into()),
+                }))
+                .await
+                .unwrap();
+            assert_eq!(last.tools[1].name, "search_issues");
+        }
+
+        #[tokio::test]
+        async fn test_calls_through_alias() {
+            let client = connect(
+                &["issues_search"],
+                ToolAliases::new()
+                    .alias("search_issues", "issues_search")
+                    .mark_deprecations(),
+            )
+            .await;
+
+            let result = client.call_tool(call("search_issues")).await.unwrap();
+            assert_eq!(result.content[0].as_text().unwrap().text, "issues_search");
+            assert_eq!(
+                result.meta.unwrap().get(DEPRECATION_META_KEY),
+                Some(&json!({ "alias": "search_issues", "replacement": "issues_search" }))
+            );
+
+            let result = client.call_tool(call("issues_search")).await.unwrap();
+            assert_eq!(result.meta, None);
+        }
+
+        #[tokio::test]
+        async fn test_hidden_aliases_stay_callable() {
+            let client = connect(
+                &["issues_search"],
+                ToolAliases::new()
+                    .alias("search_issues", "issues_search")
+                    .hide_aliases(),
+            )
+            .await;
+
+            assert_eq!(client.list_all_tools().await.unwrap().len(), 1);
+            assert!(client.call_tool(call("search_issues")).await.is_ok());
+        }
+
+        #[tokio::test]
+        async fn test_ignores_alias_shadowing_a_tool() {
+            let client = connect(
+                &["issues_search", "search_issues"],
+                ToolAliases::new().alias("search_issues", "issues_search"),
+            )
+            .await;
+
+            let names: Vec<_> = client
+                .list_all_tools()
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|tool| tool.name.to_string())
+                .collect();
+            assert_eq!(names, ["issues_search", "search_issues"]);
+            let result = client.call_tool(call("search_issues")).await.unwrap();
+            assert_eq!(result.content[0].as_text().unwrap().text, "search_issues");
+            assert_eq!(result.meta, None);
+        }
+
+        #[tokio::test]
+        async fn test_skips_alias_of_missing_tool() {
+            let pages = Arc::new(AtomicUsize::new(0));
+            let tools = Catalogue {
+                names: &["issues_search", "issues_list"],
+                pages: pages.clone(),
+            };
+            let client = serve(
+                tools,
+                ToolAliases::new()
+                    .alias("search_issues", "issues_search")
+                    .alias("find_issues", "issues_find"),
+            )
+            .await;
+            let resume = || {
+                client.list_tools(Some(PaginatedRequestParams {
+                    meta: None,
+                    cursor: Some("1".into()),
+                }))
+            };
+
+            // A listing resumed on the last page fetches the list from its start once.
+            let names = |result: ListToolsResult| {
+                result
+                    .tools
+                    .into_iter()
+                    .map(|tool| tool.name.to_string())
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(
+                names(resume().await.unwrap()),
+                ["issues_list", "search_issues"]
+            );
+            assert_eq!(pages.load(Ordering::Relaxed), 3);
+            // The missing target is remembered, so resuming again fetches nothing more.
+            assert_eq!(
+                names(resume().await.unwrap()),
+                ["issues_list", "search_issues"]
+            );
+            assert_eq!(pages.load(Ordering::Relaxed), 4);
+        }
+    }
+}
@@ -0,0 +1,504 @@
+//! Audit logging of tool calls.
+//!
+//! [`Audited`] wraps a tools provider and reports every `call_tool` to an
+//! [`AuditSink`] as an [`AuditRecord`]: caller, tool, arguments with configured fields
+//! redacted, result status and duration.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, LineWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use rmcp::{
+    model::{
+        CallToolRequestParams, CallToolResult, ErrorCode, ErrorData, JsonObject, ListToolsResult,
+        PaginatedRequestParams,
+    },
+    service::{RequestContext, RoleServer},
+};
+use serde_json::{Value, json};
+
+use crate::auth::Identity;
+use crate::providers::ToolsProvider;
+use crate::session::SessionId;
+
+/// Value replacing redacted argument fields.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Outcome of an audited tool call.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AuditStatus {
+    /// The tool returned a successful result.
+    Success,
+    /// The tool returned a result flagged with `is_error`.
+    ToolError,
+    /// The call failed with a protocol error.
+    Failed {
+        /// Error code of the failure.
+        code: ErrorCode,
+        /// Error message of the failure.
+        message: String,
+    },
+    /// The call was dropped before finishing, such as when the client cancelled it.
+    Cancelled,
+    /// The tools provider panicked.
+    Panicked,
+}
+
+/// A tool call reported to an [`AuditSink`].
+#[derive(Clone, Debug)]
+pub struct AuditRecord {
+    /// When the call started.
+    pub timestamp: SystemTime,
+    /// Session the call was made in.
+    pub session: Option<SessionId>,
+    /// Authenticated subject of the caller, if any.
+    pub subject: Option<String>,
+    /// Name of the called tool.
+    pub tool: String,
+    /// Call arguments, with redacted fields replaced by [`REDACTED`].
+    pub arguments: Option<JsonObject>,
+    /// Outcome of the call.
+    pub status: AuditStatus,
+    /// Time spent in the tools provider.
+    pub duration: Duration,
+}
+
+impl AuditRecord {
+    /// Convert the record to a JSON object, as written by [`JsonLines`].
+    pub fn to_json(&self) -> Value {
+        let timestamp = self
+            .timestamp
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let mut record = json!({
+            "timestamp_ms": timestamp.as_millis() as u64,
+            "session": self.session.map(|session| session.as_u64()),
+            "subject": self.subject,
+            "tool": self.tool,
+            "arguments": self.arguments,
+            "duration_ms": self.duration.as_secs_f64() * 1000.0,
+        });
+        let status = match &self.status {
+            AuditStatus::Success => json!({ "status": "success" }),
+            AuditStatus::ToolError => json!({ "status": "tool_error" }),
+            AuditStatus::Failed { code, message } => json!({
+                "status": "failed",
+                "error_code": code.0,
+                "error_message": message,
+            }),
+            AuditStatus::Cancelled => json!({ "status": "cancelled" }),
+            AuditStatus::Panicked => json!({ "status": "panicked" }),
+        };
+        if let (Value::Object(record), Value::Object(status)) = (&mut record, status) {
+            record.extend(status);
+        }
+        record
+    }
+}
+
+/// Destination of audit records.
+///
+/// Implemented for closures taking an [`AuditRecord`], for [`mpsc::Sender`] and for
+/// [`JsonLines`]. Sinks are called inline after each tool call and should not block
+/// for long.
+pub trait AuditSink: Send + Sync + 'static {
+    /// Store or forward a record.
+    fn record(&self, record: AuditRecord);
+}
+
+impl<F> AuditSink for F
+where
+    F: Fn(AuditRecord) + Send + Sync + 'static,
+{
+    fn record(&self, record: AuditRecord) {
+        self(record)
+    }
+}
+
+impl AuditSink for mpsc::Sender<AuditRecord> {
+    fn record(&self, record: AuditRecord) {
+        // Records are dropped once the receiver is gone.
+        let _ = self.send(record);
+    }
+}
+
+/// Sink writing one JSON object per line.
+///
+/// Records are queued and written by a background thread, so tool calls never wait on
+/// the writer; each line is flushed as soon as it is complete. Dropping the sink waits
+/// for the queued records to be written. Write errors are kept and can be retrieved
+/// with [`take_error`](Self::take_error).
+pub struct JsonLines<W: Write> {
+    lines: Option<mpsc::Sender<String>>,
+    writer: Option<JoinHandle<LineWriter<W>>>,
+    error: Arc<Mutex<Option<io::Error>>>,
+}
+
+impl JsonLines<File> {
+    /// Append records to a file, creating it if needed.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self::new(file))
+    }
+}
+
+impl<W: Write + Send + 'static> JsonLines<W> {
+    /// Write records to a writer.
+    pub fn new(writer: W) -> Self {
+        let (lines, queue) = mpsc::channel::<String>();
+        let error = Arc::new(Mutex::new(None));
+        let errors = error.clone();
+        let writer = thread::spawn(move || {
+            let mut writer = LineWriter::new(writer);
+            for line in queue {
+                if let Err(error) = writer.write_all(line.as_bytes()) {
+                    *errors.lock().unwrap() = Some(error);
+                }
+            }
+            writer
+        });
+        Self {
+            lines: Some(lines),
+            writer: Some(writer),
+            error,
+        }
+    }
+}
+
+impl<W: Write> JsonLines<W> {
+    /// Take the last write error, if any.
+    pub fn take_error(&self) -> Option<io::Error> {
+        self.error.lock().unwrap().take()
+    }
+
+    /// Write the queued records and get back the writer.
+    fn finish(&mut self) -> Option<LineWriter<W>> {
+        drop(self.lines.take());
+        self.writer.take()?.join().ok()
+    }
+}
+
+impl<W: Write> Drop for JsonLines<W> {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+impl<W: Write> fmt::Debug for JsonLines<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JsonLines").finish_non_exhaustive()
+    }
+}
+
+impl<W: Write + Send + 'static> AuditSink for JsonLines<W> {
+    fn record(&self, record: AuditRecord) {
+        let mut line = record.to_json().to_string();
+        line.push('\n');
+        if let Some(lines) = &self.lines {
+            // The writer thread only stops once the sender is dropped.
+            let _ = lines.send(line);
+        }
+    }
+}
+
+/// Audit configuration: a sink and the argument fields to redact.
+///
+/// Fields are given either as top-level argument names (`"password"`) or as JSON
+/// pointers to nested object fields (`"/auth/token"`).
+///
+/// # Example
+///
+/// ```no_run
+/// use rmcp_server_builder::{AuditLog, JsonLines};
+///
+/// # fn main() -> std::io::Result<()> {
+/// let audit = AuditLog::new(JsonLines::open("audit.jsonl")?)
+///     .redact_all(["api_key"])
+///     .redact("login", ["password", "/mfa/code"]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct AuditLog {
+    sink: Arc<dyn AuditSink>,
+    redact_all: Vec<String>,
+    redact: HashMap<String, Vec<String>>,
+}
+
+impl AuditLog {
+    /// Report tool calls to a sink.
+    pub fn new(sink: impl AuditSink) -> Self {
+        Self {
+            sink: Arc::new(sink),
+            redact_all: Vec::new(),
+            redact: HashMap::new(),
+        }
+    }
+
+    /// Redact fields from the arguments of every tool.
+    pub fn redact_all<S: Into<String>>(mut self, fields: impl IntoIterator<Item = S>) -> Self {
+        self.redact_all.extend(fields.into_iter().map(Into::into));
+        self
+    }
+
+    /// Redact fields from the arguments of a tool.
+    pub fn redact<S: Into<String>>(
+        mut self,
+        tool: impl Into<String>,
+        fields: impl IntoIterator<Item = S>,
+    ) -> Self {
+        self.redact
+            .entry(tool.into())
+            .or_default()
+            .extend(fields.into_iter().map(Into::into));
+        self
+    }
+
+    /// Copy the arguments of a call with the configured fields redacted.
+    pub fn redacted_arguments(&self, tool: &str, arguments: &JsonObject) -> JsonObject {
+        let mut arguments = arguments.clone();
+        let fields = self
+            .redact_all
+            .iter()
+            .chain(self.redact.get(tool).into_iter().flatten());
+        for field in fields {
+            redact_field(&mut arguments, field);
+        }
+        arguments
+    }
+}
+
+impl fmt::Debug for AuditLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuditLog")
+            .field("redact_all", &self.redact_all)
+            .field("redact", &self.redact)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Replace a top-level field or a JSON pointer target with [`REDACTED`].
+fn redact_field(arguments: &mut JsonObject, field: &str) {
+    let Some(pointer) = field.strip_prefix('/') else {
+        if let Some(value) = arguments.get_mut(field) {
+            *value = Value::String(REDACTED.into());
+        }
+        return;
+    };
+    let mut segments = pointer
+        .split('/')
+        .map(|segment| segment.replace("~1", "/").replace("~0", "~"));
+    let Some(last) = segments.next_back() else {
+        return;
+    };
+    let mut object = arguments;
+    for segment in segments {
+        match object.get_mut(&segment).and_then(Value::as_object_mut) {
+            Some(next) => object = next,
+            None => return,
+        }
+    }
+    if let Some(value) = object.get_mut(&last) {
+        *value = Value::String(REDACTED.into());
+    }
+}
+
+/// Tool call in progress, reported when it finishes or is dropped unfinished.
+struct InFlight<'a> {
+    sink: &'a dyn AuditSink,
+    record: Option<AuditRecord>,
+    started: Instant,
+}
+
+impl InFlight<'_> {
+    fn report(&mut self, status: AuditStatus) {
+        if let Some(mut record) = self.record.take() {
+            record.status = status;
+            record.duration = self.started.elapsed();
+            self.sink.record(record);
+        }
+    }
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        let status = match thread::panicking() {
+            true => AuditStatus::Panicked,
+            false => AuditStatus::Cancelled,
+        };
+        self.report(status);
+    }
+}
+
+/// Tools provider reporting every tool call to an [`AuditLog`].
+///
+/// Calls dropped before finishing are reported as [`AuditStatus::Cancelled`], and
+/// calls during which the provider panicked as [`AuditStatus::Panicked`].
+///
+/// Usually configured through [`ServerBuilder::audit_tools`](crate::ServerBuilder::audit_tools).
+#[derive(Clone, Debug)]
+pub struct Audited<T> {
+    inner: T,
+    log: AuditLog,
+}
+
+impl<T> Audited<T> {
+    /// Wrap a tools provider with an audit log.
+    pub fn new(inner: T, log: AuditLog) -> Self {
+        Self { inner, log }
+    }
+
+    /// Get a reference to the wrapped provider.
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: ToolsProvider> ToolsProvider for Audited<T> {
+    async fn list_tools(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        self.inner.list_tools(request, context).await
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let tool = request.name.to_string();
+        let arguments = request
+            .arguments
+            .as_ref()
+            .map(|arguments| self.log.redacted_arguments(&tool, arguments));
+        let session = SessionId::from_context(&context);
+        let subject = Identity::from_context(&context).and_then(|i| i.subject().map(Into::into));
+
+        let mut call = InFlight {
+            sink: &*self.log.sink,
+            record: Some(AuditRecord {
+                timestamp: SystemTime::now(),
+                session,
+                subject,
+                tool,
+                arguments,
+                status: AuditStatus::Cancelled,
+                duration: Duration::ZERO,
+            }),
+            started: Instant::now(),
+        };
+        let result = self.inner.call_tool(request, context).await;
+
+        call.report(match &result {
+            Ok(result) if result.is_error == Some(true) => AuditStatus::ToolError,
+            Ok(_) => AuditStatus::Success,
+            Err(error) => AuditStatus::Failed {
+                code: error.code,
+                message: error.message.to_string(),
+            },
+        });
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(value: Value) -> JsonObject {
+        match value {
+            Value::Object(object) => object,
+            _ => panic!("expected an object"),
+        }
+    }
+
+    #[test]
+    fn test_redaction() {
+        let log = AuditLog::new(|_: AuditRecord| {})
+            .redact_all(["api_key"])
+            .redact("login", ["password", "/mfa/code", "/a~1b"]);
+        let arguments = object(json!({
+            "user": "alice",
+            "password": "hunter2",
+            "api_key": "k",
+            "mfa": { "code": "123456", "method": "totp" },
+            "a/b": 1,
+        }));
+
+        assert_eq!(
+            Value::Object(log.redacted_arguments("login", &arguments)),
+            json!({
+                "user": "alice",
+                "password": REDACTED,
+                "api_key": REDACTED,
+                "mfa": { "code": REDACTED, "method": "totp" },
+                "a/b": REDACTED,
+            })
+        );
+        let other = log.redacted_arguments("search", &arguments);
+        assert_eq!(other["password"], "hunter2");
+        assert_eq!(other["api_key"], REDACTED);
+    }
+
+    fn record(status: AuditStatus) -> AuditRecord {
+        AuditRecord {
+            timestamp: UNIX_EPOCH + Duration::from_secs(1),
+            session: None,
+            subject: Some("alice".into()),
+            tool: "search".into(),
+            arguments: None,
+            status,
+            duration: Duration::from_millis(2),
+        }
+    }
+
+    #[test]
+    fn test_json_lines() {
+        let mut sink = JsonLines::new(Vec::new());
+        sink.record(record(AuditStatus::Failed {
+            code: ErrorCode::INVALID_PARAMS,
+            message: "bad".into(),
+        }));
+
+        let written = sink.finish().unwrap().into_inner().unwrap();
+        let line: Value = serde_json::from_slice(&written).unwrap();
+        assert!(written.ends_with(b"\n"));
+        assert_eq!(line["timestamp_ms"], 1000);
+        assert_eq!(line["subject"], "alice");
+        assert_eq!(line["status"], "failed");
+        assert_eq!(line["error_code"], ErrorCode::INVALID_PARAMS.0);
+    }
+
+    #[test]
+    fn test_unfinished_calls_are_reported() {
+        let (sender, receiver) = mpsc::channel();
+        let in_flight = || InFlight {
+            sink: &sender,
+            record: Some(record(AuditStatus::Success)),
+            started: Instant::now(),
+        };
+
+        let mut call = in_flight();
+        call.report(AuditStatus::ToolError);
+        drop(call);
+        assert_eq!(receiver.try_recv().unwrap().status, AuditStatus::ToolError);
+        assert!(receiver.try_recv().is_err(), "reported once");
+
+        drop(in_flight());
+        assert_eq!(receiver.try_recv().unwrap().status, AuditStatus::Cancelled);
+
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _call = in_flight();
+            panic!("provider bug");
+        }));
+        assert!(panicked.is_err());
+        assert_eq!(receiver.try_recv().unwrap().status, AuditStatus::Panicked);
+    }
+}
@@ -0,0 +1,369 @@
+//! Authorization of requests before they reach providers.
+//!
+//! An [`Authorizer`] configured with
+//! [`ServerBuilder::authorizer`](crate::ServerBuilder::authorizer) sees every request
+//! dispatched by the composed server, together with the caller [`Identity`] found in
+//! the request context, and can deny it before any provider is called. [`Policy`]
+//! implements common scope and role checks.
+
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::Arc;
+
+use rmcp::{
+    model::{ErrorCode, ErrorData},
+    service::{RequestContext, RoleServer},
+};
+
+use crate::filter::glob_match;
+use crate::request::{Capability, RequestKind};
+
+/// Error code returned for denied requests.
+pub const ACCESS_DENIED: ErrorCode = ErrorCode(-32003);
+
+/// Create an "access denied" error.
+pub fn access_denied(message: impl Into<Cow<'static, str>>) -> ErrorData {
+    ErrorData::new(ACCESS_DENIED, message, None)
+}
+
+/// The authenticated caller of a request.
+///
+/// Transports or middleware insert an `Identity` into the request extensions; it is
+/// then available to authorizers and providers through [`Identity::from_context`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Identity {
+    subject: Option<String>,
+    scopes: BTreeSet<String>,
+    roles: BTreeSet<String>,
+}
+
+impl Identity {
+    /// Create an identity for an authenticated subject.
+    pub fn new(subject: impl Into<String>) -> Self {
+        Self {
+            subject: Some(subject.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Create an identity without a subject.
+    pub fn anonymous() -> Self {
+        Self::default()
+    }
+
+    /// Grant a scope.
+    pub fn with_scope(mut self, scope: impl Into<String>) -> Self {
+        self.scopes.insert(scope.into());
+        self
+    }
+
+    /// Grant several scopes, e.g. from a space-separated OAuth `scope` claim.
+    pub fn with_scopes<S: Into<String>>(mut self, scopes: impl IntoIterator<Item = S>) -> Self {
+        self.scopes.extend(scopes.into_iter().map(Into::into));
+        self
+    }
+
+    /// Grant a role.
+    pub fn with_role(mut self, role: impl Into<String>) -> Self {
+        self.roles.insert(role.into());
+        self
+    }
+
+    /// Get the authenticated subject, if any.
+    pub fn subject(&self) -> Option<&str> {
+        self.subject.as_deref()
+    }
+
+    /// Check whether a scope was granted.
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.contains(scope)
+    }
+
+    /// Check whether a role was granted.
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.contains(role)
+    }
+
+    /// Iterate over the granted scopes.
+    pub fn scopes(&self) -> impl Iterator<Item = &str> {
+        self.scopes.iter().map(String::as_str)
+    }
+
+    /// Iterate over the granted roles.
+    pub fn roles(&self) -> impl Iterator<Item = &str> {
+        self.roles.iter().map(String::as_str)
+    }
+
+    /// Get the identity stored in the request extensions.
+    pub fn from_context(context: &RequestContext<RoleServer>) -> Option<Self> {
+        context.extensions.get::<Self>().cloned()
+    }
+}
+
+/// Hook deciding whether a request may reach its provider.
+///
+/// Closures taking the request and the caller identity and returning a `bool` implement
+/// this trait.
+pub trait Authorizer: Send + Sync + 'static {
+    /// Extract the caller identity from the request context.
+    ///
+    /// Defaults to the [`Identity`] stored in the request extensions.
+    fn identity(&self, context: &RequestContext<RoleServer>) -> Option<Identity> {
+        Identity::from_context(context)
+    }
+
+    /// Allow or deny a request.
+    ///
+    /// Returning an error denies the request; the error is sent back to the client.
+    fn authorize(
+        &self,
+        request: &RequestKind,
+        identity: Option<&Identity>,
+    ) -> Result<(), ErrorData>;
+}
+
+impl<F> Authorizer for F
+where
+    F: Fn(&RequestKind, Option<&Identity>) -> bool + Send + Sync + 'static,
+{
+    fn authorize(
+        &self,
+        request: &RequestKind,
+        identity: Option<&Identity>,
+    ) -> Result<(), ErrorData> {
+        if self(request, identity) {
+            Ok(())
+        } else {
+            Err(access_denied(format!("access denied: {request}")))
+        }
+    }
+}
+
+// =============================================================================
+// Policy
+// =============================================================================
+
+/// Requests a [`Policy`] rule applies to.
+#[derive(Clone, Debug)]
+pub enum Match {
+    /// Every request.
+    Any,
+    /// Every request of a capability.
+    Capability(Capability),
+    /// Calls of tools whose name matches a glob pattern.
+    Tool(String),
+    /// Requests for prompts whose name matches a glob pattern.
+    Prompt(String),
+    /// Requests for resources whose URI starts with a prefix.
+    Resource(String),
+}
+
+impl Match {
+    /// Match every request.
+    pub fn any() -> Self {
+        Match::Any
+    }
+
+    /// Match every request of a capability.
+    pub fn capability(capability: Capability) -> Self {
+        Match::Capability(capability)
+    }
+
+    /// Match calls of tools whose name matches a glob pattern.
+    ///
+    /// Calls through an alias set with
+    /// [`ServerBuilder::alias_tools`](crate::ServerBuilder::alias_tools) match the name
+    /// of the tool the alias refers to.
+    pub fn tool(pattern: impl Into<String>) -> Self {
+        Match::Tool(pattern.into())
+    }
+
+    /// Match requests for prompts whose name matches a glob pattern.
+    pub fn prompt(pattern: impl Into<String>) -> Self {
+        Match::Prompt(pattern.into())
+    }
+
+    /// Match requests for resources whose URI starts with `prefix`.
+    pub fn resource(prefix: impl Into<String>) -> Self {
+        Match::Resource(prefix.into())
+    }
+
+    fn matches(&self, request: &RequestKind) -> bool {
+        match self {
+            Match::Any => true,
+            Match::Capability(capability) => request.capability() == *capability,
+            Match::Tool(pattern) => request.tool_name().is_some_and(|n| glob_match(pattern, n)),
+            Match::Prompt(pattern) => request
+                .prompt_name()
+                .is_some_and(|n| glob_match(pattern, n)),
+            Match::Resource(prefix) => request
+                .resource_uri()
+                .is_some_and(|u| u.starts_with(prefix.as_str())),
+        }
+    }
+}
+
+/// What a [`Policy`] rule requires from the caller.
+#[derive(Clone, Debug)]
+pub enum Require {
+    /// An identity with a subject.
+    Authenticated,
+    /// An identity granted a scope.
+    Scope(String),
+    /// An identity granted a role.
+    Role(String),
+}
+
+impl Require {
+    /// Require an authenticated caller.
+    pub fn authenticated() -> Self {
+        Require::Authenticated
+    }
+
+    /// Require a scope.
+    pub fn scope(scope: impl Into<String>) -> Self {
+        Require::Scope(scope.into())
+    }
+
+    /// Require a role.
+    pub fn role(role: impl Into<String>) -> Self {
+        Require::Role(role.into())
+    }
+
+    fn check(&self, request: &RequestKind, identity: Option<&Identity>) -> Result<(), ErrorData> {
+        let satisfied = match self {
+            Require::Authenticated => identity.is_some_and(|i| i.subject().is_some()),
+            Require::Scope(scope) => identity.is_some_and(|i| i.has_scope(scope)),
+            Require::Role(role) => identity.is_some_and(|i| i.has_role(role)),
+        };
+        if satisfied {
+            return Ok(());
+        }
+        Err(access_denied(match self {
+            Require::Authenticated => format!("access denied: {request} requires authentication"),
+            Require::Scope(scope) => format!("access denied: {request} requires scope '{scope}'"),
+            Require::Role(role) => format!("access denied: {request} requires role '{role}'"),
+        }))
+    }
+}
+
+type IdentityExtractor = Arc<dyn Fn(&RequestContext<RoleServer>) -> Option<Identity> + Send + Sync>;
+
+/// Rule-based [`Authorizer`] checking scopes and roles.
+///
+/// A request is allowed when every rule matching it is satisfied; requests matched by
+/// no rule are allowed.
+///
+/// # Example
+///
+/// ```
+/// use rmcp_server_builder::{Capability, Match, Policy, Require};
+///
+/// let policy = Policy::new()
+///     .rule(Match::any(), Require::authenticated())
+///     .rule(Match::capability(Capability::Resources), Require::scope("resources:read"))
+///     .rule(Match::tool("deploy_*"), Require::role("ops"));
+/// ```
+#[derive(Clone, Default)]
+pub struct Policy {
+    rules: Vec<(Match, Require)>,
+    identity: Option<IdentityExtractor>,
+}
+
+impl Policy {
+    /// Create a policy allowing every request.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a rule requiring `require` for requests matching `target`.
+    pub fn rule(mut self, target: Match, require: Require) -> Self {
+        self.rules.push((target, require));
+        self
+    }
+
+    /// Extract the caller identity with a custom function instead of reading the
+    /// [`Identity`] stored in the request extensions.
+    pub fn identity_from(
+        mut self,
+        extract: impl Fn(&RequestContext<RoleServer>) -> Option<Identity> + Send + Sync + 'static,
+    ) -> Self {
+        self.identity = Some(Arc::new(extract));
+        self
+    }
+}
+
+impl fmt::Debug for Policy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Policy")
+            .field("rules", &self.rules)
+            .field("custom_identity", &self.identity.is_some())
+            .finish()
+    }
+}
+
+impl Authorizer for Policy {
+    fn identity(&self, context: &RequestContext<RoleServer>) -> Option<Identity> {
+        match &self.identity {
+            Some(extract) => extract(context),
+            None => Identity::from_context(context),
+        }
+    }
+
+    fn authorize(
+        &self,
+        request: &RequestKind,
+        identity: Option<&Identity>,
+    ) -> Result<(), ErrorData> {
+        self.rules
+            .iter()
+            .filter(|(target, _)| target.matches(request))
+            .try_for_each(|(_, require)| require.check(request, identity))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(name: &str) -> RequestKind {
+        RequestKind::CallTool { name: name.into() }
+    }
+
+    #[test]
+    fn test_policy_rules() {
+        let policy = Policy::new()
+            .rule(Match::any(), Require::authenticated())
+            .rule(Match::tool("deploy_*"), Require::role("ops"))
+            .rule(Match::resource("secret://"), Require::scope("secrets"));
+
+        let user = Identity::new("alice");
+        let ops = Identity::new("bob").with_role("ops");
+
+        assert!(policy.authorize(&call("search"), None).is_err());
+        assert!(policy.authorize(&call("search"), Some(&user)).is_ok());
+        assert!(policy.authorize(&call("deploy_prod"), Some(&user)).is_err());
+        assert!(policy.authorize(&call("deploy_prod"), Some(&ops)).is_ok());
+
+        let read = RequestKind::ReadResource {
+            uri: "secret://db".into(),
+        };
+        let err = policy.authorize(&read, Some(&ops)).unwrap_err();
+        assert_eq!(err.code, ACCESS_DENIED);
+        assert!(
+            policy
+                .authorize(&read, Some(&ops.clone().with_scope("secrets")))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_closure_authorizer() {
+        let authorizer = |request: &RequestKind, _: Option<&Identity>| {
+            request.capability() != Capability::Logging
+        };
+        assert!(authorizer.authorize(&call("search"), None).is_ok());
+        assert!(authorizer.authorize(&RequestKind::SetLevel, None).is_err());
+    }
+}
@@ -1,26 +1,44 @@
 //! Builder for composing MCP servers from individual capability providers.
 
-use rmcp::model::{Implementation, ProtocolVersion, ServerCapabilities, ServerInfo};
+use std::sync::Arc;
 
+use rmcp::model::{
+    Implementation, InitializeRequestParams, ProtocolVersion, ServerCapabilities, ServerInfo,
+};
+
+use crate::alias::{AliasedTools, ToolAliases};
+use crate::audit::{AuditLog, Audited};
+use crate::auth::Authorizer;
+use crate::cache::{CachedLists, CachedResources, CachedTools, ListCache, ResultCache};
+use crate::circuit_breaker::{CircuitBreaker, GuardedTools};
+use crate::factory::{ServerFactory, Shared, SharedServer};
+use crate::filter::{FilteredTools, ToolFilter};
 use crate::providers::{
     CompletionProvider, LoggingProvider, PromptsProvider, ResourcesProvider, ServerInfoProvider,
     ToolsProvider,
 };
-use crate::server::{Server, Unset};
+use crate::rate_limit::{RateLimited, RateLimits};
+use crate::request::RequestKind;
+use crate::retry::{Retry, RetryPolicy};
+use crate::rewrite::{RewrittenTools, ToolOverrides};
+use crate::server::{PanicHook, Server, SessionInit, Unset};
+use crate::session::{SessionId, SessionState};
 
 /// Builder for constructing a composed MCP server.
 ///
 /// # Example
 ///
-/// ```ignore
-/// use rmcp_server_builder::ServerBuilder;
+/// ```no_run
+/// use rmcp_server_builder::{PromptsProvider, ServerBuilder, ToolsProvider};
 /// use rmcp::model::Implementation;
 ///
+/// # fn compose(my_tools_provider: impl ToolsProvider, my_prompts_provider: impl PromptsProvider) {
 /// let server = ServerBuilder::new()
 ///     .info(Implementation::from_build_env())
 ///     .tools(my_tools_provider)
 ///     .prompts(my_prompts_provider)
 ///     .build();
+/// # }
 /// ```
 pub struct ServerBuilder<T, P, R, C, L, I> {
     tools: Option<T>,
@@ -30,6 +48,11 @@ pub struct ServerBuilder<T, P, R, C, L, I> {
     logging: Option<L>,
     info: Option<I>,
     instructions: Option<String>,
+    authorizer: Option<Arc<dyn Authorizer>>,
+    panic_hook: Option<PanicHook>,
+    session_init: Option<SessionInit>,
+    protocol_versions: Option<Arc<[ProtocolVersion]>>,
+    tool_aliases: Vec<ToolAliases>,
 }
 
 impl Default for ServerBuilder<Unset, Unset, Unset, Unset, Unset, Unset> {
@@ -49,6 +72,11 @@ impl ServerBuilder<Unset, Unset, Unset, Unset, Unset, Unset> {
             logging: None,
             info: None,
             instructions: None,
+            authorizer: None,
+            panic_hook: None,
+            session_init: None,
+            protocol_versions: None,
+            tool_aliases: Vec::new(),
         }
     }
 }
@@ -64,6 +92,11 @@ impl<T, P, R, C, L, I> ServerBuilder<T, P, R, C, L, I> {
             logging: self.logging,
             info: self.info,
             instructions: self.instructions,
+            authorizer: self.authorizer,
+            panic_hook: self.panic_hook,
+            session_init: self.session_init,
+            protocol_versions: self.protocol_versions,
+            tool_aliases: self.tool_aliases,
         }
     }
 
@@ -80,6 +113,11 @@ impl<T, P, R, C, L, I> ServerBuilder<T, P, R, C, L, I> {
             logging: self.logging,
             info: self.info,
             instructions: self.instructions,
+            authorizer: self.authorizer,
+            panic_hook: self.panic_hook,
+            session_init: self.session_init,
+            protocol_versions: self.protocol_versions,
+            tool_aliases: self.tool_aliases,
         }
     }
 
@@ -96,6 +134,11 @@ impl<T, P, R, C, L, I> ServerBuilder<T, P, R, C, L, I> {
             logging: self.logging,
             info: self.info,
             instructions: self.instructions,
+            authorizer: self.authorizer,
+            panic_hook: self.panic_hook,
+            session_init: self.session_init,
+            protocol_versions: self.protocol_versions,
+            tool_aliases: self.tool_aliases,
         }
     }
 
@@ -112,6 +155,11 @@ impl<T, P, R, C, L, I> ServerBuilder<T, P, R, C, L, I> {
             logging: self.logging,
             info: self.info,
             instructions: self.instructions,
+            authorizer: self.authorizer,
+            panic_hook: self.panic_hook,
+            session_init: self.session_init,
+            protocol_versions: self.protocol_versions,
+            tool_aliases: self.tool_aliases,
         }
     }
 
@@ -128,6 +176,11 @@ impl<T, P, R, C, L, I> ServerBuilder<T, P, R, C, L, I> {
             logging: Some(provider),
             info: self.info,
             instructions: self.instructions,
+            authorizer: self.authorizer,
+            panic_hook: self.panic_hook,
+            session_init: self.session_init,
+            protocol_versions: self.protocol_versions,
+            tool_aliases: self.tool_aliases,
         }
     }
 
@@ -144,6 +197,11 @@ impl<T, P, R, C, L, I> ServerBuilder<T, P, R, C, L, I> {
             logging: self.logging,
             info: Some(provider),
             instructions: self.instructions,
+            authorizer: self.authorizer,
+            panic_hook: self.panic_hook,
+            session_init: self.session_init,
+            protocol_versions: self.protocol_versions,
+            tool_aliases: self.tool_aliases,
         }
     }
 
@@ -152,6 +210,331 @@ impl<T, P, R, C, L, I> ServerBuilder<T, P, R, C, L, I> {
         self.instructions = Some(instructions.into());
         self
     }
+
+    /// Set the authorizer checking every request before it reaches a provider.
+    pub fn authorizer(mut self, authorizer: impl Authorizer) -> Self {
+        self.authorizer = Some(Arc::new(authorizer));
+        self
+    }
+
+    /// Set a hook called when a provider panics while handling a request.
+    ///
+    /// Panics are always caught and turned into an internal error for the request; the
+    /// hook receives the request and the panic message, e.g. to report it.
+    pub fn panic_hook(mut self, hook: impl Fn(&RequestKind, &str) + Send + Sync + 'static) -> Self {
+        self.panic_hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Set a hook filling the state of every session when a client initializes it.
+    ///
+    /// The hook receives the initialize request and the session's [`SessionState`],
+    /// emptied of any value from an earlier initialization except the
+    /// [`ConnectedClient`](crate::ConnectedClient).
+    pub fn init_session(
+        mut self,
+        init: impl Fn(&InitializeRequestParams, &SessionState) + Send + Sync + 'static,
+    ) -> Self {
+        self.session_init = Some(Arc::new(init));
+        self
+    }
+
+    /// Set the protocol versions the server supports.
+    ///
+    /// On initialization, the server answers with the version requested by the client
+    /// when supported, or else with the newest supported version older than it.
+    /// Clients requesting a version older than every supported one are rejected with an
+    /// invalid params error listing the supported versions. Without this, the server
+    /// answers with the info provider's protocol version.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `versions` is empty, since the server could not accept any client.
+    pub fn protocol_versions(
+        mut self,
+        versions: impl IntoIterator<Item = ProtocolVersion>,
+    ) -> Self {
+        let versions: Arc<[ProtocolVersion]> = versions.into_iter().collect();
+        assert!(
+            !versions.is_empty(),
+            "at least one protocol version must be supported"
+        );
+        self.protocol_versions = Some(versions);
+        self
+    }
+
+    /// Restrict the tools exposed by the tools provider.
+    ///
+    /// Tools rejected by the filter are hidden from `list_tools` and cannot be called.
+    pub fn filter_tools(self, filter: ToolFilter) -> ServerBuilder<FilteredTools<T>, P, R, C, L, I>
+    where
+        T: ToolsProvider,
+    {
+        ServerBuilder {
+            tools: self.tools.map(|tools| FilteredTools::new(tools, filter)),
+            prompts: self.prompts,
+            resources: self.resources,
+            completion: self.completion,
+            logging: self.logging,
+            info: self.info,
+            instructions: self.instructions,
+            authorizer: self.authorizer,
+            panic_hook: self.panic_hook,
+            session_init: self.session_init,
+            protocol_versions: self.protocol_versions,
+            tool_aliases: self.tool_aliases,
+        }
+    }
+
+    /// Override the metadata of selected tools of the tools provider.
+    pub fn rewrite_tools(
+        self,
+        overrides: ToolOverrides,
+    ) -> ServerBuilder<RewrittenTools<T>, P, R, C, L, I>
+    where
+        T: ToolsProvider,
+    {
+        ServerBuilder {
+            tools: self
+                .tools
+                .map(|tools| RewrittenTools::new(tools, overrides)),
+            prompts: self.prompts,
+            resources: self.resources,
+            completion: self.completion,
+            logging: self.logging,
+            info: self.info,
+            instructions: self.instructions,
+            authorizer: self.authorizer,
+            panic_hook: self.panic_hook,
+            session_init: self.session_init,
+            protocol_versions: self.protocol_versions,
+            tool_aliases: self.tool_aliases,
+        }
+    }
+
+    /// Accept old names for renamed tools of the tools provider.
+    ///
+    /// Aliases are resolved before authorization, so the [`authorizer`](Self::authorizer)
+    /// sees calls through an alias as calls of the tool it refers to. Rate limits, circuit
+    /// breakers and result caches do the same, whichever side of the aliases they wrap.
+    pub fn alias_tools(
+        mut self,
+        aliases: ToolAliases,
+    ) -> ServerBuilder<AliasedTools<T>, P, R, C, L, I>
+    where
+        T: ToolsProvider,
+    {
+        self.tool_aliases.push(aliases.clone());
+        ServerBuilder {
+            tools: self.tools.map(|tools| AliasedTools::new(tools, aliases)),
+            prompts: self.prompts,
+            resources: self.resources,
+            completion: self.completion,
+            logging: self.logging,
+            info: self.info,
+            instructions: self.instructions,
+            authorizer: self.authorizer,
+            panic_hook: self.panic_hook,
+            session_init: self.session_init,
+            protocol_versions: self.protocol_versions,
+            tool_aliases: self.tool_aliases,
+        }
+    }
+
+    /// Enforce rate limits and concurrency caps on calls to the tools provider.
+    ///
+    /// Calls exceeding a limit fail with a [`RATE_LIMITED`](crate::RATE_LIMITED) error.
+    pub fn rate_limit_tools(
+        self,
+        limits: RateLimits,
+    ) -> ServerBuilder<RateLimited<T>, P, R, C, L, I>
+    where
+        T: ToolsProvider,
+    {
+        ServerBuilder {
+            tools: self.tools.map(|tools| RateLimited::new(tools, limits)),
+            prompts: self.prompts,
+            resources: self.resources,
+            completion: self.completion,
+            logging: self.logging,
+            info: self.info,
+            instructions: self.instructions,
+            authorizer: self.authorizer,
+            panic_hook: self.panic_hook,
+            session_init: self.session_init,
+            protocol_versions: self.protocol_versions,
+            tool_aliases: self.tool_aliases,
+        }
+    }
+
+    /// Retry failed requests to the tools provider according to `policy`.
+    pub fn retry_tools(self, policy: RetryPolicy) -> ServerBuilder<Retry<T>, P, R, C, L, I>
+    where
+        T: ToolsProvider,
+    {
+        ServerBuilder {
+            tools: self.tools.map(|tools| Retry::new(tools, policy)),
+            prompts: self.prompts,
+            resources: self.resources,
+            completion: self.completion,
+            logging: self.logging,
+            info: self.info,
+            instructions: self.instructions,
+            authorizer: self.authorizer,
+            panic_hook: self.panic_hook,
+            session_init: self.session_init,
+            protocol_versions: self.protocol_versions,
+            tool_aliases: self.tool_aliases,
+        }
+    }
+
+    /// Retry failed requests to the resources provider according to `policy`.
+    pub fn retry_resources(self, policy: RetryPolicy) -> ServerBuilder<T, P, Retry<R>, C, L, I>
+    where
+        R: ResourcesProvider,
+    {
+        ServerBuilder {
+            tools: self.tools,
+            prompts: self.prompts,
+            resources: self
+                .resources
+                .map(|resources| Retry::new(resources, policy)),
+            completion: self.completion,
+            logging: self.logging,
+            info: self.info,
+            instructions: self.instructions,
+            authorizer: self.authorizer,
+            panic_hook: self.panic_hook,
+            session_init: self.session_init,
+            protocol_versions: self.protocol_versions,
+            tool_aliases: self.tool_aliases,
+        }
+    }
+
+    /// Fail calls to the tools provider fast while the circuit of the called tool is open.
+    pub fn guard_tools(
+        self,
+        breaker: CircuitBreaker,
+    ) -> ServerBuilder<GuardedTools<T>, P, R, C, L, I>
+    where
+        T: ToolsProvider,
+    {
+        ServerBuilder {
+            tools: self.tools.map(|tools| GuardedTools::new(tools, breaker)),
+            prompts: self.prompts,
+            resources: self.resources,
+            completion: self.completion,
+            logging: self.logging,
+            info: self.info,
+            instructions: self.instructions,
+            authorizer: self.authorizer,
+            panic_hook: self.panic_hook,
+            session_init: self.session_init,
+            protocol_versions: self.protocol_versions,
+            tool_aliases: self.tool_aliases,
+        }
+    }
+
+    /// Report every call to the tools provider to an audit log.
+    pub fn audit_tools(self, log: AuditLog) -> ServerBuilder<Audited<T>, P, R, C, L, I>
+    where
+        T: ToolsProvider,
+    {
+        ServerBuilder {
+            tools: self.tools.map(|tools| Audited::new(tools, log)),
+            prompts: self.prompts,
+            resources: self.resources,
+            completion: self.completion,
+            logging: self.logging,
+            info: self.info,
+            instructions: self.instructions,
+            authorizer: self.authorizer,
+            panic_hook: self.panic_hook,
+            session_init: self.session_init,
+            protocol_versions: self.protocol_versions,
+            tool_aliases: self.tool_aliases,
+        }
+    }
+
+    /// Serve calls of the tools selected in the cache configuration from `cache`.
+    pub fn cache_tools(self, cache: ResultCache) -> ServerBuilder<CachedTools<T>, P, R, C, L, I>
+    where
+        T: ToolsProvider,
+    {
+        ServerBuilder {
+            tools: self.tools.map(|tools| CachedTools::new(tools, cache)),
+            prompts: self.prompts,
+            resources: self.resources,
+            completion: self.completion,
+            logging: self.logging,
+            info: self.info,
+            instructions: self.instructions,
+            authorizer: self.authorizer,
+            panic_hook: self.panic_hook,
+            session_init: self.session_init,
+            protocol_versions: self.protocol_versions,
+            tool_aliases: self.tool_aliases,
+        }
+    }
+
+    /// Serve resource reads from `cache`.
+    pub fn cache_resources(
+        self,
+        cache: ResultCache,
+    ) -> ServerBuilder<T, P, CachedResources<R>, C, L, I>
+    where
+        R: ResourcesProvider,
+    {
+        ServerBuilder {
+            tools: self.tools,
+            prompts: self.prompts,
+            resources: self
+                .resources
+                .map(|resources| CachedResources::new(resources, cache)),
+            completion: self.completion,
+            logging: self.logging,
+            info: self.info,
+            instructions: self.instructions,
+            authorizer: self.authorizer,
+            panic_hook: self.panic_hook,
+            session_init: self.session_init,
+            protocol_versions: self.protocol_versions,
+            tool_aliases: self.tool_aliases,
+        }
+    }
+
+    /// Memoize the `list_*` results of the tools, prompts and resources providers in
+    /// `cache`.
+    pub fn cache_lists(
+        self,
+        cache: ListCache,
+    ) -> ServerBuilder<CachedLists<T>, CachedLists<P>, CachedLists<R>, C, L, I>
+    where
+        T: ToolsProvider,
+        P: PromptsProvider,
+        R: ResourcesProvider,
+    {
+        ServerBuilder {
+            tools: self
+                .tools
+                .map(|tools| CachedLists::new(tools, cache.clone())),
+            prompts: self
+                .prompts
+                .map(|prompts| CachedLists::new(prompts, cache.clone())),
+            resources: self
+                .resources
+                .map(|resources| CachedLists::new(resources, cache)),
+            completion: self.completion,
+            logging: self.logging,
+            info: self.info,
+            instructions: self.instructions,
+            authorizer: self.authorizer,
+            panic_hook: self.panic_hook,
+            session_init: self.session_init,
+            protocol_versions: self.protocol_versions,
+            tool_aliases: self.tool_aliases,
+        }
+    }
 }
 
 // Build method - requires I to be set
@@ -172,8 +555,51 @@ where
             logging: self.logging,
             info: self.info.expect("info provider is required"),
             instructions: self.instructions,
+            authorizer: self.authorizer,
+            panic_hook: self.panic_hook,
+            session_init: self.session_init,
+            protocol_versions: self.protocol_versions,
+            tool_aliases: self.tool_aliases.into(),
+            session_id: SessionId::next(),
+            session_state: SessionState::default(),
         }
     }
+
+    /// Build a factory creating a server for every session.
+    ///
+    /// Providers are wrapped in [`Shared`] so that the servers of all sessions use
+    /// the same provider values. Each server has its own [`SessionId`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if no info provider was set.
+    pub fn build_factory(self) -> ServerFactory<SharedServer<T, P, R, C, L, I>>
+    where
+        T: Send + Sync + 'static,
+        P: Send + Sync + 'static,
+        R: Send + Sync + 'static,
+        C: Send + Sync + 'static,
+        L: Send + Sync + 'static,
+        I: Send + Sync + 'static,
+    {
+        let server = Server {
+            tools: self.tools.map(Shared::new),
+            prompts: self.prompts.map(Shared::new),
+            resources: self.resources.map(Shared::new),
+            completion: self.completion.map(Shared::new),
+            logging: self.logging.map(Shared::new),
+            info: Shared::new(self.info.expect("info provider is required")),
+            instructions: self.instructions,
+            authorizer: self.authorizer,
+            panic_hook: self.panic_hook,
+            session_init: self.session_init,
+            protocol_versions: self.protocol_versions,
+            tool_aliases: self.tool_aliases.into(),
+            session_id: SessionId::next(),
+            session_state: SessionState::default(),
+        };
+        ServerFactory::new(move || server.clone())
+    }
 }
 
 // =============================================================================
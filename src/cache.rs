@@ -0,0 +1,1040 @@
+//! Result caching for resource reads and idempotent tool calls.
+//!
+//! [`CachedResources`] and [`CachedTools`] wrap providers and serve repeated
+//! `read_resource` and `call_tool` requests from a shared [`ResultCache`]. Entries
+//! expire after a TTL, the cache holds a bounded number of entries, and resources are
+//! invalidated when their update notification is sent through
+//! [`ResultCache::notify_resource_updated`]. Notifications sent directly through a
+//! peer bypass the cache and leave stale contents until they expire.
+//!
+//! [`CachedLists`] memoizes `list_*` results in a [`ListCache`], which is invalidated
+//! when list changes are signalled through its `notify_*_list_changed` methods.
+//!
+//! Both caches are also invalidated by the list changes and resource updates that a
+//! [`ProxyProvider`](crate::ProxyProvider) or [`Gateway`](crate::Gateway) below them
+//! forwards from remote servers.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rmcp::{
+    Peer,
+    model::{
+        CallToolRequestParams, CallToolResult, ErrorData, GetPromptRequestParams, GetPromptResult,
+        JsonObject, ListPromptsResult, ListResourceTemplatesResult, ListResourcesResult,
+        ListToolsResult, PaginatedRequestParams, ReadResourceRequestParams, ReadResourceResult,
+        ResourceUpdatedNotificationParam, SubscribeRequestParams, UnsubscribeRequestParams,
+    },
+    service::{RequestContext, RoleServer, ServiceError},
+};
+use serde_json::Value;
+
+use crate::alias::AliasChain;
+use crate::providers::{PromptsProvider, ResourcesProvider, ToolsProvider};
+use crate::session::SessionId;
+
+/// Default time to live of cached entries.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(60);
+
+/// Default maximum number of cached entries of each kind.
+pub const DEFAULT_MAX_ENTRIES: usize = 1024;
+
+/// Configuration of a [`ResultCache`].
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use rmcp_server_builder::{CacheConfig, ResultCache};
+///
+/// let cache = ResultCache::new(
+///     CacheConfig::new()
+///         .ttl(Duration::from_secs(300))
+///         .max_entries(256)
+///         .read_only_tools()
+///         .tool("get_weather"),
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct CacheConfig {
+    ttl: Duration,
+    max_entries: usize,
+    tools: HashSet<String>,
+    read_only_tools: bool,
+    per_session: bool,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl: DEFAULT_TTL,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            tools: HashSet::new(),
+            read_only_tools: false,
+            per_session: false,
+        }
+    }
+}
+
+impl CacheConfig {
+    /// Create a configuration with the default TTL and size bound, caching no tools.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set how long entries stay valid.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Set the maximum number of cached entries of each kind.
+    ///
+    /// When full, the least recently used entry is evicted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_entries` is zero.
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        assert!(max_entries > 0, "cache must hold at least one entry");
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Cache calls of a tool.
+    pub fn tool(mut self, name: impl Into<String>) -> Self {
+        self.tools.insert(name.into());
+        self
+    }
+
+    /// Cache calls of every tool listed with `read_only_hint` set.
+    ///
+    /// Tools are recognized from the results of `list_tools` going through
+    /// [`CachedTools`].
+    pub fn read_only_tools(mut self) -> Self {
+        self.read_only_tools = true;
+        self
+    }
+
+    /// Keep separate entries per session instead of sharing them across sessions.
+    ///
+    /// Use this when results depend on the caller.
+    pub fn per_session(mut self) -> Self {
+        self.per_session = true;
+        self
+    }
+}
+
+// =============================================================================
+// Store
+// =============================================================================
+
+#[derive(Debug)]
+struct Slot<V> {
+    value: V,
+    expires: Instant,
+    used: u64,
+}
+
+#[derive(Debug)]
+struct Slots<K, V> {
+    slots: HashMap<K, Slot<V>>,
+    clock: u64,
+    generation: u64,
+}
+
+/// Bounded map of expiring entries with least-recently-used eviction.
+///
+/// Each invalidation bumps a generation, so values computed before an invalidation are
+/// not stored after it.
+#[derive(Debug)]
+pub(crate) struct Store<K, V> {
+    ttl: Duration,
+    max_entries: usize,
+    inner: Mutex<Slots<K, V>>,
+}
+
+impl<K: Clone + Eq + Hash, V: Clone> Store<K, V> {
+    pub(crate) fn new(ttl: Duration, max_entries: usize) -> Self {
+        Self {
+            ttl,
+            max_entries,
+            inner: Mutex::new(Slots {
+                slots: HashMap::new(),
+                clock: 0,
+                generation: 0,
+            }),
+        }
+    }
+
+    /// Look up a value, returning the current generation on a miss.
+    pub(crate) fn get(&self, key: &K, now: Instant) -> Result<V, u64> {
+        let inner = &mut *self.inner.lock().unwrap();
+        let Some(slot) = inner.slots.get_mut(key) else {
+            return Err(inner.generation);
+        };
+        if slot.expires <= now {
+            inner.slots.remove(key);
+            return Err(inner.generation);
+        }
+        inner.clock += 1;
+        slot.used = inner.clock;
+        Ok(slot.value.clone())
+    }
+
+    /// Store a value unless the store was invalidated since `generation`.
+    pub(crate) fn insert(&self, key: K, value: V, now: Instant, generation: u64) {
+        let inner = &mut *self.inner.lock().unwrap();
+        if inner.generation != generation {
+            return;
+        }
+        let slots = &mut inner.slots;
+        if slots.len() >= self.max_entries && !slots.contains_key(&key) {
+            slots.retain(|_, slot| slot.expires > now);
+        }
+        if slots.len() >= self.max_entries
+            && !slots.contains_key(&key)
+            && let Some(oldest) = slots
+                .iter()
+                .min_by_key(|(_, slot)| slot.used)
+                .map(|(key, _)| key.clone())
+        {
+            slots.remove(&oldest);
+        }
+        inner.clock += 1;
+        slots.insert(
+            key,
+            Slot {
+                value,
+                expires: now + self.ttl,
+                used: inner.clock,
+            },
+        );
+    }
+
+    pub(crate) fn remove_if(&self, mut predicate: impl FnMut(&K) -> bool) {
+        let inner = &mut *self.inner.lock().unwrap();
+        inner.generation += 1;
+        inner.slots.retain(|key, _| !predicate(key));
+    }
+}
+
+// =============================================================================
+// Cache handle
+// =============================================================================
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct ResourceKey {
+    session: Option<SessionId>,
+    uri: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct ToolKey {
+    session: Option<SessionId>,
+    name: String,
+    arguments: String,
+}
+
+#[derive(Debug)]
+struct Shared {
+    config: CacheConfig,
+    resources: Store<ResourceKey, ReadResourceResult>,
+    tools: Store<ToolKey, CallToolResult>,
+    read_only: Mutex<HashSet<String>>,
+}
+
+/// Shared cache of resource reads and tool call results.
+///
+/// Clones refer to the same cache: keep one to invalidate entries while the server
+/// runs, and pass others to [`ServerBuilder::cache_resources`](crate::ServerBuilder::cache_resources)
+/// and [`ServerBuilder::cache_tools`](crate::ServerBuilder::cache_tools).
+///
+/// # Resource updates
+///
+/// Updates that a `ProxyProvider` or `Gateway` forwards from a remote server invalidate
+/// the cache automatically. Updates sent directly through a [`Peer`] do not: the cache
+/// cannot see them, and keeps serving the old contents until they expire. Local providers must send their updates through
+/// [`notify_resource_updated`](Self::notify_resource_updated), or call
+/// [`invalidate_resource`](Self::invalidate_resource) themselves.
+#[derive(Clone, Debug)]
+pub struct ResultCache {
+    shared: Arc<Shared>,
+}
+
+impl ResultCache {
+    /// Create an empty cache.
+    pub fn new(config: CacheConfig) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                resources: Store::new(config.ttl, config.max_entries),
+                tools: Store::new(config.ttl, config.max_entries),
+                read_only: Mutex::default(),
+                config,
+            }),
+        }
+    }
+
+    /// Drop the cached contents of a resource.
+    pub fn invalidate_resource(&self, uri: &str) {
+        self.shared.resources.remove_if(|key| key.uri == uri);
+    }
+
+    /// Drop every cached result of a tool.
+    pub fn invalidate_tool(&self, name: &str) {
+        self.shared.tools.remove_if(|key| key.name == name);
+    }
+
+    /// Drop every cached entry.
+    pub fn clear(&self) {
+        self.shared.resources.remove_if(|_| true);
+        self.shared.tools.remove_if(|_| true);
+    }
+
+    /// Invalidate a resource and notify the client that it was updated.
+    ///
+    /// Providers behind [`CachedResources`] should send resource update notifications
+    /// through this method so that subsequent reads see the new contents.
+    pub async fn notify_resource_updated(
+        &self,
+        peer: &Peer<RoleServer>,
+        params: ResourceUpdatedNotificationParam,
+    ) -> Result<(), ServiceError> {
+        self.invalidate_resource(&params.uri);
+        peer.notify_resource_updated(params).await
+    }
+
+    fn session(&self, context: &RequestContext<RoleServer>) -> Option<SessionId> {
+        if self.shared.config.per_session {
+            SessionId::from_context(context)
+        } else {
+            None
+        }
+    }
+
+    fn caches_tool(&self, name: &str) -> bool {
+        self.shared.config.tools.contains(name)
+            || (self.shared.config.read_only_tools
+                && self.shared.read_only.lock().unwrap().contains(name))
+    }
+
+    fn observe_tools(&self, result: &ListToolsResult) {
+        if !self.shared.config.read_only_tools {
+            return;
+        }
+        let mut read_only = self.shared.read_only.lock().unwrap();
+        for tool in &result.tools {
+            let hint = tool.annotations.as_ref().and_then(|a| a.read_only_hint);
+            if hint == Some(true) {
+                read_only.insert(tool.name.to_string());
+            } else {
+                read_only.remove(tool.name.as_ref());
+            }
+        }
+    }
+}
+
+/// Serialize arguments with object keys sorted, so equal arguments give equal keys.
+fn canonicalize(arguments: Option<&JsonObject>) -> String {
+    fn write(value: &Value, out: &mut String) {
+        match value {
+            Value::Object(object) => {
+                let mut entries: Vec<_> = object.iter().collect();
+                entries.sort_by_key(|(key, _)| *key);
+                out.push('{');
+                for (i, (key, value)) in entries.into_iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    out.push_str(&Value::String(key.clone()).to_string());
+                    out.push(':');
+                    write(value, out);
+                }
+                out.push('}');
+            }
+            Value::Array(items) => {
+                out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    write(item, out);
+                }
+                out.push(']');
+            }
+            value => out.push_str(&value.to_string()),
+        }
+    }
+
+    let mut out = String::new();
+    if let Some(arguments) = arguments {
+        write(&Value::Object(arguments.clone()), &mut out);
+    }
+    out
+}
+
+// =============================================================================
+// Forwarded notifications
+// =============================================================================
+
+/// Caches a request went through on its way to a provider.
+///
+/// Cache adapters add their cache to the request extensions, so that providers
+/// forwarding notifications from elsewhere can invalidate them.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct CacheHooks {
+    results: Vec<ResultCache>,
+    lists: Vec<ListCache>,
+}
+
+impl CacheHooks {
+    /// Get the caches recorded in the request extensions.
+    pub(crate) fn from_context(context: &RequestContext<RoleServer>) -> Option<&Self> {
+        context.extensions.get::<Self>()
+    }
+
+    /// Record `cache` in the extensions of a request.
+    fn add_results(
+        mut context: RequestContext<RoleServer>,
+        cache: &ResultCache,
+    ) -> RequestContext<RoleServer> {
+        context
+            .extensions
+            .get_or_insert_default::<Self>()
+            .push_results(cache);
+        context
+    }
+
+    /// Record `cache` in the extensions of a request.
+    fn add_lists(
+        mut context: RequestContext<RoleServer>,
+        cache: &ListCache,
+    ) -> RequestContext<RoleServer> {
+        context
+            .extensions
+            .get_or_insert_default::<Self>()
+            .push_lists(cache);
+        context
+    }
+
+    fn push_results(&mut self, cache: &ResultCache) {
+        if !self
+            .results
+            .iter()
+            .any(|c| Arc::ptr_eq(&c.shared, &cache.shared))
+        {
+            self.results.push(cache.clone());
+        }
+    }
+
+    fn push_lists(&mut self, cache: &ListCache) {
+        if !self
+            .lists
+            .iter()
+            .any(|c| Arc::ptr_eq(&c.lists, &cache.lists))
+        {
+            self.lists.push(cache.clone());
+        }
+    }
+
+    /// Add the caches of `other` that are not recorded yet.
+    #[cfg(feature = "proxy")]
+    pub(crate) fn merge(&mut self, other: &Self) {
+        other
+            .results
+            .iter()
+            .for_each(|cache| self.push_results(cache));
+        other.lists.iter().for_each(|cache| self.push_lists(cache));
+    }
+
+    /// Tell the list caches that the cursors of the lists they cache are only valid
+    /// in the session they were issued to.
+    pub(crate) fn session_bound_cursors(&self) {
+        for cache in &self.lists {
+            cache.lists.session_bound.store(true, Ordering::Relaxed);
+        }
+    }
+
+    #[cfg(feature = "proxy")]
+    pub(crate) fn tool_list_changed(&self) {
+        self.lists.iter().for_each(ListCache::invalidate_tools);
+    }
+
+    #[cfg(feature = "proxy")]
+    pub(crate) fn prompt_list_changed(&self) {
+        self.lists.iter().for_each(ListCache::invalidate_prompts);
+    }
+
+    #[cfg(feature = "proxy")]
+    pub(crate) fn resource_list_changed(&self) {
+        self.lists.iter().for_each(ListCache::invalidate_resources);
+    }
+
+    #[cfg(feature = "proxy")]
+    pub(crate) fn resource_updated(&self, uri: &str) {
+        for cache in &self.results {
+            cache.invalidate_resource(uri);
+        }
+    }
+}
+
+// =============================================================================
+// Providers
+// =============================================================================
+
+/// Resources provider serving `read_resource` from a [`ResultCache`].
+///
+/// The wrapped provider must announce resource updates through
+/// [`ResultCache::notify_resource_updated`]; see [its invalidation
+/// rules](ResultCache#resource-updates).
+///
+/// Usually configured through
+/// [`ServerBuilder::cache_resources`](crate::ServerBuilder::cache_resources).
+#[derive(Clone, Debug)]
+pub struct CachedResources<R> {
+    inner: R,
+    cache: ResultCache,
+}
+
+impl<R> CachedResources<R> {
+    /// Wrap a resources provider with a cache.
+    pub fn new(inner: R, cache: ResultCache) -> Self {
+        Self { inner, cache }
+    }
+
+    /// Get a reference to the wrapped provider.
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    /// Get the cache used by this provider.
+    pub fn cache(&self) -> &ResultCache {
+        &self.cache
+    }
+}
+
+impl<R: ResourcesProvider> ResourcesProvider for CachedResources<R> {
+    async fn list_resources(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, ErrorData> {
+        let context = CacheHooks::add_results(context, &self.cache);
+        self.inner.list_resources(request, context).await
+    }
+
+    async fn list_resource_templates(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, ErrorData> {
+        let context = CacheHooks::add_results(context, &self.cache);
+        self.inner.list_resource_templates(request, context).await
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, ErrorData> {
+        let context = CacheHooks::add_results(context, &self.cache);
+        let key = ResourceKey {
+            session: self.cache.session(&context),
+            uri: request.uri.clone(),
+        };
+        let store = &self.cache.shared.resources;
+        let generation = match store.get(&key, Instant::now()) {
+            Ok(result) => return Ok(result),
+            Err(generation) => generation,
+        };
+        let result = self.inner.read_resource(request, context).await?;
+        store.insert(key, result.clone(), Instant::now(), generation);
+        Ok(result)
+    }
+
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        let context = CacheHooks::add_results(context, &self.cache);
+        self.inner.subscribe(request, context).await
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        let context = CacheHooks::add_results(context, &self.cache);
+        self.inner.unsubscribe(request, context).await
+    }
+}
+
+/// Tools provider serving calls of selected tools from a [`ResultCache`].
+///
+/// Only the tools selected in the [`CacheConfig`] are cached; error results and
+/// task-augmented calls are never cached.
+///
+/// Usually configured through [`ServerBuilder::cache_tools`](crate::ServerBuilder::cache_tools).
+#[derive(Clone, Debug)]
+pub struct CachedTools<T> {
+    inner: T,
+    cache: ResultCache,
+}
+
+impl<T> CachedTools<T> {
+    /// Wrap a tools provider with a cache.
+    pub fn new(inner: T, cache: ResultCache) -> Self {
+        Self { inner, cache }
+    }
+
+    /// Get a reference to the wrapped provider.
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Get the cache used by this provider.
+    pub fn cache(&self) -> &ResultCache {
+        &self.cache
+    }
+}
+
+impl<T: ToolsProvider> ToolsProvider for CachedTools<T> {
+    async fn list_tools(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        let context = CacheHooks::add_results(context, &self.cache);
+        let result = self.inner.list_tools(request, context).await?;
+        self.cache.observe_tools(&result);
+        Ok(result)
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let context = CacheHooks::add_results(context, &self.cache);
+        let name = AliasChain::canonical_name(&context, &request.name).to_owned();
+        if request.task.is_some() || !self.cache.caches_tool(&name) {
+            return self.inner.call_tool(request, context).await;
+        }
+
+        let key = ToolKey {
+            session: self.cache.session(&context),
+            name,
+            arguments: canonicalize(request.arguments.as_ref()),
+        };
+        let store = &self.cache.shared.tools;
+        let generation = match store.get(&key, Instant::now()) {
+            Ok(result) => return Ok(result),
+            Err(generation) => generation,
+        };
+        let result = self.inner.call_tool(request, context).await?;
+        if result.is_error != Some(true) {
+            store.insert(key, result.clone(), Instant::now(), generation);
+        }
+        Ok(result)
+    }
+}
+
+// =============================================================================
+// List caching
+// =============================================================================
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct ListKey {
+    session: Option<SessionId>,
+    cursor: Option<String>,
+}
+
+#[derive(Debug)]
+struct Lists {
+    per_session: bool,
+    session_bound: AtomicBool,
+    tools: Store<ListKey, ListToolsResult>,
+    prompts: Store<ListKey, ListPromptsResult>,
+    resources: Store<ListKey, ListResourcesResult>,
+    resource_templates: Store<ListKey, ListResourceTemplatesResult>,
+}
+
+/// Shared cache of `list_*` results, one entry per cursor.
+///
+/// Clones refer to the same cache. Providers behind [`CachedLists`] should signal list
+/// changes through [`notify_tool_list_changed`](Self::notify_tool_list_changed) and its
+/// siblings, which invalidate the cached lists before notifying the client. Only the
+/// TTL, size bound and [`per_session`](CacheConfig::per_session) settings of the
+/// [`CacheConfig`] apply.
+///
+/// Lists of a provider issuing cursors bound to a session, such as
+/// [`Paginated`](crate::Paginated), are always cached per session.
+#[derive(Clone, Debug)]
+pub struct ListCache {
+    lists: Arc<Lists>,
+}
+
+impl ListCache {
+    /// Create an empty cache.
+    pub fn new(config: CacheConfig) -> Self {
+        Self {
+            lists: Arc::new(Lists {
+                per_session: config.per_session,
+                session_bound: AtomicBool::new(false),
+                tools: Store::new(config.ttl, config.max_entries),
+                prompts: Store::new(config.ttl, config.max_entries),
+                resources: Store::new(config.ttl, config.max_entries),
+                resource_templates: Store::new(config.ttl, config.max_entries),
+            }),
+        }
+    }
+
+    /// Drop the cached tool lists.
+    pub fn invalidate_tools(&self) {
+        self.lists.tools.remove_if(|_| true);
+    }
+
+    /// Drop the cached prompt lists.
+    pub fn invalidate_prompts(&self) {
+        self.lists.prompts.remove_if(|_| true);
+    }
+
+    /// Drop the cached resource and resource template lists.
+    pub fn invalidate_resources(&self) {
+        self.lists.resources.remove_if(|_| true);
+        self.lists.resource_templates.remove_if(|_| true);
+    }
+
+    /// Invalidate the tool lists and notify the client that they changed.
+    pub async fn notify_tool_list_changed(
+        &self,
+        peer: &Peer<RoleServer>,
+    ) -> Result<(), ServiceError> {
+        self.invalidate_tools();
+        peer.notify_tool_list_changed().await
+    }
+
+    /// Invalidate the prompt lists and notify the client that they changed.
+    pub async fn notify_prompt_list_changed(
+        &self,
+        peer: &Peer<RoleServer>,
+    ) -> Result<(), ServiceError> {
+        self.invalidate_prompts();
+        peer.notify_prompt_list_changed().await
+    }
+
+    /// Invalidate the resource lists and notify the client that they changed.
+    pub async fn notify_resource_list_changed(
+        &self,
+        peer: &Peer<RoleServer>,
+    ) -> Result<(), ServiceError> {
+        self.invalidate_resources();
+        peer.notify_resource_list_changed().await
+    }
+
+    fn key(&self, session: Option<SessionId>, cursor: Option<String>) -> ListKey {
+        let keyed = self.lists.per_session || self.lists.session_bound.load(Ordering::Relaxed);
+        ListKey {
+            session: session.filter(|_| keyed),
+            cursor,
+        }
+    }
+
+    /// Serve the page at `cursor` from `store`, or store the result of `fetch`.
+    async fn memoize<V: Clone>(
+        &self,
+        store: &Store<ListKey, V>,
+        session: Option<SessionId>,
+        cursor: Option<String>,
+        fetch: impl Future<Output = Result<V, ErrorData>>,
+    ) -> Result<V, ErrorData> {
+        let generation = match store.get(&self.key(session, cursor.clone()), Instant::now()) {
+            Ok(result) => return Ok(result),
+            Err(generation) => generation,
+        };
+        let result = fetch.await?;
+        // The provider may have just revealed that its cursors are bound to the session.
+        let key = self.key(session, cursor);
+        store.insert(key, result.clone(), Instant::now(), generation);
+        Ok(result)
+    }
+}
+
+/// Provider memoizing `list_tools`, `list_prompts`, `list_resources` and
+/// `list_resource_templates` in a [`ListCache`].
+///
+/// Other methods are forwarded unchanged. Usually configured through
+/// [`ServerBuilder::cache_lists`](crate::ServerBuilder::cache_lists).
+#[derive(Clone, Debug)]
+pub struct CachedLists<P> {
+    inner: P,
+    cache: ListCache,
+}
+
+impl<P> CachedLists<P> {
+    /// Wrap a provider with a list cache.
+    pub fn new(inner: P, cache: ListCache) -> Self {
+        Self { inner, cache }
+    }
+
+    /// Get a reference to the wrapped provider.
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Get the cache used by this provider.
+    pub fn cache(&self) -> &ListCache {
+        &self.cache
+    }
+}
+
+impl<T: ToolsProvider> ToolsProvider for CachedLists<T> {
+    async fn list_tools(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        let context = CacheHooks::add_lists(context, &self.cache);
+        let session = SessionId::from_context(&context);
+        let cursor = request.as_ref().and_then(|r| r.cursor.clone());
+        self.cache
+            .memoize(
+                &self.cache.lists.tools,
+                session,
+                cursor,
+                self.inner.list_tools(request, context),
+            )
+            .await
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let context = CacheHooks::add_lists(context, &self.cache);
+        self.inner.call_tool(request, context).await
+    }
+}
+
+impl<P: PromptsProvider> PromptsProvider for CachedLists<P> {
+    async fn list_prompts(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, ErrorData> {
+        let context = CacheHooks::add_lists(context, &self.cache);
+        let session = SessionId::from_context(&context);
+        let cursor = request.as_ref().and_then(|r| r.cursor.clone());
+        self.cache
+            .memoize(
+                &self.cache.lists.prompts,
+                session,
+                cursor,
+                self.inner.list_prompts(request, context),
+            )
+            .await
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, ErrorData> {
+        let context = CacheHooks::add_lists(context, &self.cache);
+        self.inner.get_prompt(request, context).await
+    }
+}
+
+impl<R: ResourcesProvider> ResourcesProvider for CachedLists<R> {
+    async fn list_resources(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, ErrorData> {
+        let context = CacheHooks::add_lists(context, &self.cache);
+        let session = SessionId::from_context(&context);
+        let cursor = request.as_ref().and_then(|r| r.cursor.clone());
+        self.cache
+            .memoize(
+                &self.cache.lists.resources,
+                session,
+                cursor,
+                self.inner.list_resources(request, context),
+            )
+            .await
+    }
+
+    async fn list_resource_templates(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, ErrorData> {
+        let context = CacheHooks::add_lists(context, &self.cache);
+        let session = SessionId::from_context(&context);
+        let cursor = request.as_ref().and_then(|r| r.cursor.clone());
+        self.cache
+            .memoize(
+                &self.cache.lists.resource_templates,
+                session,
+                cursor,
+                self.inner.list_resource_templates(request, context),
+            )
+            .await
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, ErrorData> {
+        let context = CacheHooks::add_lists(context, &self.cache);
+        self.inner.read_resource(request, context).await
+    }
+
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        let context = CacheHooks::add_lists(context, &self.cache);
+        self.inner.subscribe(request, context).await
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        let context = CacheHooks::add_lists(context, &self.cache);
+        self.inner.unsubscribe(request, context).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_canonicalize_sorts_keys() {
+        let a = json!({ "b": 1, "a": { "y": [1, { "d": 2, "c": 3 }], "x": null } });
+        let b = json!({ "a": { "x": null, "y": [1, { "c": 3, "d": 2 }] }, "b": 1 });
+        assert_eq!(canonicalize(a.as_object()), canonicalize(b.as_object()));
+        assert_eq!(canonicalize(None), "");
+    }
+
+    #[test]
+    fn test_store_expiry_and_eviction() {
+        let store = Store::new(Duration::from_secs(10), 2);
+        let now = Instant::now();
+
+        store.insert("a", 1, now, 0);
+        store.insert("b", 2, now, 0);
+        assert_eq!(store.get(&"a", now), Ok(1));
+        store.insert("c", 3, now, 0);
+        assert!(
+            store.get(&"b", now).is_err(),
+            "least recently used is evicted"
+        );
+        assert_eq!(store.get(&"a", now), Ok(1));
+        assert!(store.get(&"a", now + Duration::from_secs(10)).is_err());
+    }
+
+    #[test]
+    fn test_store_invalidation_discards_stale_insert() {
+        let store = Store::new(Duration::from_secs(10), 2);
+        let now = Instant::now();
+
+        let generation = store.get(&"a", now).unwrap_err();
+        store.remove_if(|key| *key == "a");
+        store.insert("a", 1, now, generation);
+        assert!(store.get(&"a", now).is_err());
+    }
+
+    mod session {
+        use std::sync::atomic::AtomicUsize;
+
+        use rmcp::model::{CallToolRequestParams, CallToolResult, Content, Implementation, Tool};
+
+        use super::*;
+        use crate::test_support::{self, call};
+        use crate::{Paginated, ServerBuilder, ToolAliases};
+
+        /// Tools provider returning three tools at once and counting calls.
+        #[derive(Default)]
+        struct Three {
+            calls: Arc<AtomicUsize>,
+        }
+
+        impl ToolsProvider for Three {
+            async fn list_tools(
+                &self,
+                _request: Option<PaginatedRequestParams>,
+                _context: RequestContext<RoleServer>,
+            ) -> Result<ListToolsResult, ErrorData> {
+                Ok(ListToolsResult {
+                    meta: None,
+                    next_cursor: None,
+                    tools: ["a", "b", "c"]
+                        .map(|name| Tool::new(name, "", Arc::default()))
+                        .into(),
+                })
+            }
+
+            async fn call_tool(
+                &self,
+                request: CallToolRequestParams,
+                _context: RequestContext<RoleServer>,
+            ) -> Result<CallToolResult, ErrorData> {
+                self.calls.fetch_add(1, Ordering::Relaxed);
+                Ok(CallToolResult::success(vec![Content::text(
+                    request.name.to_string(),
+                )]))
+            }
+        }
+
+        #[tokio::test]
+        async fn test_session_bound_cursors_are_cached_per_session() {
+            let factory = ServerBuilder::new()
+                .info(Implementation::default())
+                .tools(Paginated::new(Three::default()).page_size(2))
+                .cache_lists(ListCache::new(CacheConfig::new()))
+                .build_factory();
+            let first = test_support::connect(factory.create(), ()).await;
+            let second = test_support::connect(factory.create(), ()).await;
+
+            // Only the first page gets cached for the first session.
+            let page = first.list_tools(None).await.unwrap();
+            assert!(page.next_cursor.is_some());
+            // The second session follows a cursor issued to itself, not a cached one.
+            assert_eq!(second.list_all_tools().await.unwrap().len(), 3);
+        }
+
+        #[tokio::test]
+        async fn test_caches_aliases_as_their_target() {
+            let tools = Three::default();
+            let calls = tools.calls.clone();
+            let cache = ResultCache::new(CacheConfig::new().tool("a"));
+            let server = ServerBuilder::new()
+                .info(Implementation::default())
+                .tools(tools)
+                .alias_tools(ToolAliases::new().alias("old_a", "a"))
+                .cache_tools(cache.clone())
+                .build();
+            let client = test_support::connect(server, ()).await;
+
+            client.call_tool(call("old_a")).await.unwrap();
+            client.call_tool(call("a")).await.unwrap();
+            assert_eq!(calls.load(Ordering::Relaxed), 1);
+            cache.invalidate_tool("a");
+            client.call_tool(call("old_a")).await.unwrap();
+            assert_eq!(calls.load(Ordering::Relaxed), 2);
+        }
+    }
+}
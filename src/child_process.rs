@@ -0,0 +1,301 @@
+//! Providers backed by MCP servers running as child processes.
+//!
+//! [`ChildProcessProvider`] spawns a command, speaks MCP over its stdin and stdout,
+//! and forwards requests to it like a [`ProxyProvider`](crate::ProxyProvider). The
+//! process is started on first use and restarted with backoff when it exits.
+
+use std::fmt;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use rmcp::{
+    model::{
+        CallToolRequestParams, CallToolResult, CompleteRequestParams, CompleteResult, ErrorData,
+        GetPromptRequestParams, GetPromptResult, ListPromptsResult, ListResourceTemplatesResult,
+        ListResourcesResult, ListToolsResult, PaginatedRequestParams, ReadResourceRequestParams,
+        ReadResourceResult, SubscribeRequestParams, UnsubscribeRequestParams,
+    },
+    service::{RequestContext, RoleServer},
+    transport::TokioChildProcess,
+};
+use tokio::process::Command;
+use tokio::task::JoinHandle;
+
+use crate::gateway::{ConnectionState, Link, connector};
+use crate::providers::{CompletionProvider, PromptsProvider, ResourcesProvider, ToolsProvider};
+use crate::proxy::ProxyProvider;
+use crate::retry::Backoff;
+
+/// Provider forwarding requests to an MCP server running as a child process.
+///
+/// - The process is spawned from a fresh [`Command`] on first use. Its stderr is
+///   inherited.
+/// - When the process exits, the next request restarts it. Processes that exit again
+///   shortly after starting, or fail to start, are restarted with backoff; meanwhile
+///   requests fail with an internal error carrying `retryAfterMs`.
+/// - [`supervise`](Self::supervise) restarts crashed processes in the background
+///   instead of waiting for the next request.
+///
+/// Clones share the same process, which is killed when the last clone is dropped.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use rmcp::model::Implementation;
+/// use rmcp_server_builder::{ChildProcessProvider, ServerBuilder};
+/// use tokio::process::Command;
+///
+/// # fn compose(info: Implementation) {
+/// let git = ChildProcessProvider::new(|| {
+///     let mut command = Command::new("mcp-server-git");
+///     command.arg("--repository").arg(".");
+///     command
+/// });
+/// git.supervise(Duration::from_secs(1));
+///
+/// let server = ServerBuilder::new()
+///     .info(info)
+///     .tools(git.clone())
+///     .resources(git)
+///     .build();
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ChildProcessProvider {
+    link: Arc<Link>,
+    backoff: Backoff,
+}
+
+impl ChildProcessProvider {
+    /// Run the server started by the commands `command` builds.
+    pub fn new<F>(command: F) -> Self
+    where
+        F: Fn() -> Command + Send + Sync + 'static,
+    {
+        let name = command()
+            .as_std()
+            .get_program()
+            .to_string_lossy()
+            .into_owned();
+        let spawn = move || {
+            let command = command();
+            async move { TokioChildProcess::new(command) }
+        };
+        Self {
+            link: Arc::new(Link::new(name, connector(spawn))),
+            backoff: Backoff::default(),
+        }
+    }
+
+    /// Set the delays between restarts of a failing process.
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Start the process now instead of on first use.
+    pub async fn start(&self) -> Result<(), ErrorData> {
+        self.provider().await.map(drop)
+    }
+
+    /// Get the state of the connection to the process.
+    pub fn state(&self) -> ConnectionState {
+        self.link.state()
+    }
+
+    /// Check every `interval` whether the process exited, and restart it if so.
+    ///
+    /// The task stops once the provider and all its clones are dropped.
+    pub fn supervise(&self, interval: Duration) -> JoinHandle<()> {
+        let link = Arc::downgrade(&self.link);
+        let backoff = self.backoff;
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticks.tick().await;
+                let Some(link) = Weak::upgrade(&link) else {
+                    return;
+                };
+                link.recover(&backoff).await;
+            }
+        })
+    }
+
+    async fn provider(&self) -> Result<ProxyProvider, ErrorData> {
+        self.link.provider(&self.backoff).await
+    }
+}
+
+impl fmt::Debug for ChildProcessProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChildProcessProvider")
+            .field("link", &self.link)
+            .field("backoff", &self.backoff)
+            .finish()
+    }
+}
+
+impl ToolsProvider for ChildProcessProvider {
+    async fn list_tools(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        self.provider().await?.list_tools(request, context).await
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.provider().await?.call_tool(request, context).await
+    }
+}
+
+impl PromptsProvider for ChildProcessProvider {
+    async fn list_prompts(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, ErrorData> {
+        self.provider().await?.list_prompts(request, context).await
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, ErrorData> {
+        self.provider().await?.get_prompt(request, context).await
+    }
+}
+
+impl ResourcesProvider for ChildProcessProvider {
+    async fn list_resources(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, ErrorData> {
+        self.provider()
+            .await?
+            .list_resources(request, context)
+            .await
+    }
+
+    async fn list_resource_templates(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, ErrorData> {
+        self.provider()
+            .await?
+            .list_resource_templates(request, context)
+            .await
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, ErrorData> {
+        self.provider().await?.read_resource(request, context).await
+    }
+
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        self.provider().await?.subscribe(request, context).await
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        self.provider().await?.unsubscribe(request, context).await
+    }
+}
+
+impl CompletionProvider for ChildProcessProvider {
+    async fn complete(
+        &self,
+        request: CompleteRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CompleteResult, ErrorData> {
+        self.provider().await?.complete(request, context).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_restart_backoff() {
+        let provider = ChildProcessProvider::new(|| Command::new("/nonexistent/mcp-server"))
+            .backoff(Backoff::fixed(Duration::from_secs(60)));
+        assert_eq!(provider.state(), ConnectionState::Disconnected);
+
+        let error = provider.start().await.unwrap_err();
+        assert!(error.message.contains("/nonexistent/mcp-server"));
+        let ConnectionState::Failed { retry_after, .. } = provider.state() else {
+            panic!("expected a failed connection");
+        };
+        assert!(retry_after > Duration::from_secs(50));
+
+        // Waiting for the backoff delay, no new process is spawned.
+        let error = provider.start().await.unwrap_err();
+        assert!(error.data.unwrap()["retryAfterMs"].as_u64().unwrap() > 50_000);
+    }
+
+    /// Server answering `initialize`, then exiting once initialized. Each start is
+    /// recorded as a line in the file given as first argument.
+    const EXITS_AFTER_INITIALIZE: &str = r#"
+        echo start >> "$1"
+        read -r request
+        id=$(printf '%s' "$request" | sed 's/.*"id":\([0-9]*\).*/\1/')
+        printf '{"jsonrpc":"2.0","id":%s,"result":{"protocolVersion":"2025-06-18","capabilities":{},"serverInfo":{"name":"exits","version":"1.0.0"}}}\n' "$id"
+        read -r initialized
+    "#;
+
+    #[tokio::test]
+    async fn test_supervise_restarts_exited_process() {
+        let starts = std::env::temp_dir().join(format!("mcp-starts-{}", std::process::id()));
+        let _ = std::fs::remove_file(&starts);
+        let path = starts.clone();
+        let provider = ChildProcessProvider::new(move || {
+            let mut command = Command::new("sh");
+            command
+                .arg("-c")
+                .arg(EXITS_AFTER_INITIALIZE)
+                .arg("sh")
+                .arg(&path);
+            command
+        })
+        .backoff(Backoff::fixed(Duration::from_millis(50)));
+        let count = || {
+            std::fs::read_to_string(&starts)
+                .map(|starts| starts.lines().count())
+                .unwrap_or(0)
+        };
+
+        provider.start().await.unwrap();
+        assert_eq!(count(), 1);
+        let supervisor = provider.supervise(Duration::from_millis(10));
+        for _ in 0..200 {
+            if count() >= 3 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        supervisor.abort();
+        let restarted = count() >= 3;
+        let _ = std::fs::remove_file(&starts);
+        assert!(restarted);
+    }
+}
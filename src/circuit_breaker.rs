@@ -0,0 +1,581 @@
+//! Circuit breaking for tools backed by unreliable services.
+//!
+//! [`GuardedTools`] wraps a tools provider and tracks the outcome of recent calls of
+//! each tool in a [`CircuitBreaker`]. When the failure rate of a tool crosses a
+//! threshold its circuit opens and calls fail fast with [`CIRCUIT_OPEN`]; after a
+//! cool-down, a few probe calls decide whether the circuit closes again.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rmcp::{
+    model::{
+        CallToolRequestParams, CallToolResult, ErrorCode, ErrorData, ListToolsResult,
+        PaginatedRequestParams,
+    },
+    service::{RequestContext, RoleServer},
+};
+use serde_json::json;
+
+use crate::alias::AliasChain;
+use crate::providers::ToolsProvider;
+
+/// Error code returned for calls rejected by an open circuit.
+pub const CIRCUIT_OPEN: ErrorCode = ErrorCode(-32030);
+
+/// Number of tracked circuits above which healthy closed circuits are dropped.
+const PRUNE_THRESHOLD: usize = 4096;
+
+/// State of the circuit of a tool.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Calls go through and their outcomes are tracked.
+    Closed,
+    /// Calls fail fast until the cool-down ends.
+    Open {
+        /// Time left before probe calls are allowed.
+        retry_after: Duration,
+    },
+    /// A limited number of probe calls go through to test the tool.
+    HalfOpen,
+}
+
+/// Snapshot of the circuit of a tool, as returned by [`CircuitBreaker::status`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct CircuitStatus {
+    /// Current state.
+    pub state: CircuitState,
+    /// Calls in the tracking window.
+    pub calls: usize,
+    /// Failed calls in the tracking window.
+    pub failures: usize,
+    /// Number of times the circuit opened.
+    pub trips: u64,
+}
+
+impl CircuitStatus {
+    /// Fraction of failed calls in the tracking window.
+    pub fn failure_rate(&self) -> f64 {
+        if self.calls == 0 {
+            0.0
+        } else {
+            self.failures as f64 / self.calls as f64
+        }
+    }
+}
+
+type Classifier = Arc<dyn Fn(&Result<CallToolResult, ErrorData>) -> bool + Send + Sync>;
+
+#[derive(Clone)]
+struct Config {
+    window: usize,
+    minimum_calls: usize,
+    failure_rate: f64,
+    open_duration: Duration,
+    probes: usize,
+    is_failure: Classifier,
+}
+
+#[derive(Debug)]
+enum Phase {
+    Closed,
+    Open { until: Instant },
+    HalfOpen { in_flight: usize, succeeded: usize },
+}
+
+#[derive(Debug)]
+struct Circuit {
+    phase: Phase,
+    outcomes: VecDeque<bool>,
+    trips: u64,
+}
+
+/// How a call was admitted through a circuit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Admission {
+    Normal,
+    Probe,
+}
+
+impl Circuit {
+    fn new() -> Self {
+        Self {
+            phase: Phase::Closed,
+            outcomes: VecDeque::new(),
+            trips: 0,
+        }
+    }
+
+    fn failures(&self) -> usize {
+        self.outcomes.iter().filter(|failed| **failed).count()
+    }
+
+    /// Check whether the circuit behaves like a new one.
+    fn is_healthy(&self) -> bool {
+        matches!(self.phase, Phase::Closed) && self.failures() == 0
+    }
+
+    /// Admit a call, or return how long to wait before the circuit allows calls.
+    fn admit(&mut self, config: &Config, now: Instant) -> Result<Admission, Duration> {
+        match &mut self.phase {
+            Phase::Closed => Ok(Admission::Normal),
+            Phase::Open { until } if now < *until => Err(*until - now),
+            Phase::Open { .. } => {
+                self.phase = Phase::HalfOpen {
+                    in_flight: 1,
+                    succeeded: 0,
+                };
+                Ok(Admission::Probe)
+            }
+            Phase::HalfOpen { in_flight, .. } if *in_flight < config.probes => {
+                *in_flight += 1;
+                Ok(Admission::Probe)
+            }
+            Phase::HalfOpen { .. } => Err(Duration::ZERO),
+        }
+    }
+
+    /// Record the outcome of an admitted call; `None` for calls that did not complete.
+    fn record(
+        &mut self,
+        config: &Config,
+        admission: Admission,
+        failed: Option<bool>,
+        now: Instant,
+    ) {
+        match (&mut self.phase, admission) {
+            (
+                Phase::HalfOpen {
+                    in_flight,
+                    succeeded,
+                },
+                Admission::Probe,
+            ) => {
+                *in_flight -= 1;
+                match failed {
+                    Some(true) => self.trip(config, now),
+                    Some(false) => {
+                        *succeeded += 1;
+                        if *succeeded >= config.probes {
+                            self.phase = Phase::Closed;
+                            self.outcomes.clear();
+                        }
+                    }
+                    None => {}
+                }
+            }
+            (Phase::Closed, Admission::Normal) => {
+                let Some(failed) = failed else {
+                    return;
+                };
+                self.outcomes.push_back(failed);
+                if self.outcomes.len() > config.window {
+                    self.outcomes.pop_front();
+                }
+                let calls = self.outcomes.len();
+                if calls >= config.minimum_calls
+                    && self.failures() as f64 / calls as f64 >= config.failure_rate
+                {
+                    self.trip(config, now);
+                }
+            }
+            // Outcomes of calls admitted before the last state change are stale.
+            _ => {}
+        }
+    }
+
+    fn trip(&mut self, config: &Config, now: Instant) {
+        self.phase = Phase::Open {
+            until: now + config.open_duration,
+        };
+        self.trips += 1;
+    }
+
+    fn status(&self, now: Instant) -> CircuitStatus {
+        let state = match self.phase {
+            Phase::Closed => CircuitState::Closed,
+            Phase::Open { until } if now < until => CircuitState::Open {
+                retry_after: until - now,
+            },
+            Phase::Open { .. } | Phase::HalfOpen { .. } => CircuitState::HalfOpen,
+        };
+        CircuitStatus {
+            state,
+            calls: self.outcomes.len(),
+            failures: self.failures(),
+            trips: self.trips,
+        }
+    }
+}
+
+/// Per-tool circuit breakers with a status API.
+///
+/// The circuit of a tool opens when, among its last `window` calls (and at least
+/// `minimum_calls`), the fraction of failures reaches `failure_rate`. It stays open for
+/// `open_duration`, then lets `probes` calls through: if they all succeed the circuit
+/// closes, and any failure opens it again.
+///
+/// By default, a call fails when it returns an internal error; the window holds 20
+/// calls, at least 5 calls are required, the failure rate threshold is 50%, circuits
+/// stay open for 30 seconds and a single probe is used.
+///
+/// Clones share their circuits: keep one to query [`status`](Self::status) while the
+/// server runs. A circuit is tracked once a call of its tool completes; when many tools
+/// are tracked, closed circuits without recent failures are forgotten.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use rmcp_server_builder::CircuitBreaker;
+///
+/// let breaker = CircuitBreaker::new()
+///     .failure_rate(0.25)
+///     .open_duration(Duration::from_secs(10))
+///     .count_tool_errors();
+/// ```
+#[derive(Clone)]
+pub struct CircuitBreaker {
+    config: Config,
+    circuits: Arc<Mutex<BTreeMap<String, Circuit>>>,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self {
+            config: Config {
+                window: 20,
+                minimum_calls: 5,
+                failure_rate: 0.5,
+                open_duration: Duration::from_secs(30),
+                probes: 1,
+                is_failure: Arc::new(
+                    |result| matches!(result, Err(error) if error.code == ErrorCode::INTERNAL_ERROR),
+                ),
+            },
+            circuits: Arc::default(),
+        }
+    }
+}
+
+impl CircuitBreaker {
+    /// Create circuit breakers with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the number of recent calls tracked per tool.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window` is zero.
+    pub fn window(mut self, window: usize) -> Self {
+        assert!(window > 0, "window must track at least one call");
+        self.config.window = window;
+        self
+    }
+
+    /// Set the number of tracked calls required before a circuit can open.
+    pub fn minimum_calls(mut self, minimum_calls: usize) -> Self {
+        self.config.minimum_calls = minimum_calls;
+        self
+    }
+
+    /// Set the fraction of failed calls, between 0 and 1, that opens a circuit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `failure_rate` is not above 0 and at most 1.
+    pub fn failure_rate(mut self, failure_rate: f64) -> Self {
+        assert!(
+            failure_rate > 0.0 && failure_rate <= 1.0,
+            "failure rate must be above 0 and at most 1"
+        );
+        self.config.failure_rate = failure_rate;
+        self
+    }
+
+    /// Set how long an open circuit rejects calls before probing.
+    pub fn open_duration(mut self, open_duration: Duration) -> Self {
+        self.config.open_duration = open_duration;
+        self
+    }
+
+    /// Set the number of successful probe calls required to close a circuit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `probes` is zero.
+    pub fn probes(mut self, probes: usize) -> Self {
+        assert!(probes > 0, "at least one probe is required");
+        self.config.probes = probes;
+        self
+    }
+
+    /// Decide which call outcomes count as failures.
+    pub fn failure_if(
+        mut self,
+        is_failure: impl Fn(&Result<CallToolResult, ErrorData>) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.config.is_failure = Arc::new(is_failure);
+        self
+    }
+
+    /// Also count results flagged with `is_error` as failures.
+    pub fn count_tool_errors(self) -> Self {
+        let is_failure = self.config.is_failure.clone();
+        self.failure_if(move |result| {
+            is_failure(result) || matches!(result, Ok(result) if result.is_error == Some(true))
+        })
+    }
+
+    /// Get the status of the circuit of a tool, if it was called.
+    pub fn status(&self, tool: &str) -> Option<CircuitStatus> {
+        let circuits = self.circuits.lock().unwrap();
+        circuits
+            .get(tool)
+            .map(|circuit| circuit.status(Instant::now()))
+    }
+
+    /// Get the status of the circuits of every called tool, sorted by tool name.
+    pub fn statuses(&self) -> Vec<(String, CircuitStatus)> {
+        let now = Instant::now();
+        let circuits = self.circuits.lock().unwrap();
+        circuits
+            .iter()
+            .map(|(tool, circuit)| (tool.clone(), circuit.status(now)))
+            .collect()
+    }
+
+    /// Close the circuit of a tool and forget its history.
+    pub fn reset(&self, tool: &str) {
+        self.circuits.lock().unwrap().remove(tool);
+    }
+
+    fn admit(&self, tool: &str, now: Instant) -> Result<Admission, Duration> {
+        let mut circuits = self.circuits.lock().unwrap();
+        match circuits.get_mut(tool) {
+            Some(circuit) => circuit.admit(&self.config, now),
+            // Untracked tools have a closed circuit.
+            None => Ok(Admission::Normal),
+        }
+    }
+
+    fn record(&self, tool: &str, admission: Admission, failed: Option<bool>, now: Instant) {
+        let mut circuits = self.circuits.lock().unwrap();
+        if !circuits.contains_key(tool) {
+            if failed.is_none() {
+                return;
+            }
+            if circuits.len() >= PRUNE_THRESHOLD {
+                circuits.retain(|_, circuit| !circuit.is_healthy());
+            }
+        }
+        circuits
+            .entry(tool.to_owned())
+            .or_insert_with(Circuit::new)
+            .record(&self.config, admission, failed, now);
+    }
+}
+
+impl fmt::Debug for CircuitBreaker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CircuitBreaker")
+            .field("window", &self.config.window)
+            .field("minimum_calls", &self.config.minimum_calls)
+            .field("failure_rate", &self.config.failure_rate)
+            .field("open_duration", &self.config.open_duration)
+            .field("probes", &self.config.probes)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Releases an admitted call that did not complete, e.g. because it was cancelled.
+struct Pending<'a> {
+    breaker: &'a CircuitBreaker,
+    tool: &'a str,
+    admission: Admission,
+    done: bool,
+}
+
+impl Drop for Pending<'_> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.breaker
+                .record(self.tool, self.admission, None, Instant::now());
+        }
+    }
+}
+
+/// Tools provider failing fast on tools whose circuit is open.
+///
+/// Usually configured through [`ServerBuilder::guard_tools`](crate::ServerBuilder::guard_tools).
+#[derive(Clone, Debug)]
+pub struct GuardedTools<T> {
+    inner: T,
+    breaker: CircuitBreaker,
+}
+
+impl<T> GuardedTools<T> {
+    /// Wrap a tools provider with circuit breakers.
+    pub fn new(inner: T, breaker: CircuitBreaker) -> Self {
+        Self { inner, breaker }
+    }
+
+    /// Get a reference to the wrapped provider.
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Get the circuit breakers of this provider.
+    pub fn breaker(&self) -> &CircuitBreaker {
+        &self.breaker
+    }
+}
+
+impl<T: ToolsProvider> ToolsProvider for GuardedTools<T> {
+    async fn list_tools(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        self.inner.list_tools(request, context).await
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let tool = AliasChain::canonical_name(&context, &request.name).to_owned();
+        let admission = match self.breaker.admit(&tool, Instant::now()) {
+            Ok(admission) => admission,
+            Err(retry_after) => {
+                return Err(ErrorData::new(
+                    CIRCUIT_OPEN,
+                    format!(
+                        "tool '{tool}' is temporarily unavailable after repeated failures; \
+                         retry in {}s",
+                        retry_after.as_secs_f64().ceil() as u64
+                    ),
+                    Some(json!({ "retryAfterMs": retry_after.as_millis() as u64 })),
+                ));
+            }
+        };
+
+        let mut pending = Pending {
+            breaker: &self.breaker,
+            tool: &tool,
+            admission,
+            done: false,
+        };
+        let result = self.inner.call_tool(request, context).await;
+        pending.done = true;
+        let failed = (self.breaker.config.is_failure)(&result);
+        self.breaker
+            .record(&tool, admission, Some(failed), Instant::now());
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker() -> CircuitBreaker {
+        CircuitBreaker::new()
+            .window(4)
+            .minimum_calls(4)
+            .failure_rate(0.5)
+            .open_duration(Duration::from_secs(10))
+    }
+
+    #[test]
+    fn test_circuit_opens_and_recovers() {
+        let breaker = breaker();
+        let now = Instant::now();
+        for failed in [false, true, false] {
+            let admission = breaker.admit("fetch", now).unwrap();
+            breaker.record("fetch", admission, Some(failed), now);
+        }
+        assert_eq!(breaker.status("fetch").unwrap().state, CircuitState::Closed);
+
+        let admission = breaker.admit("fetch", now).unwrap();
+        breaker.record("fetch", admission, Some(true), now);
+        assert_eq!(
+            breaker.admit("fetch", now + Duration::from_secs(4)),
+            Err(Duration::from_secs(6))
+        );
+        assert_eq!(breaker.status("fetch").unwrap().trips, 1);
+
+        let later = now + Duration::from_secs(10);
+        let probe = breaker.admit("fetch", later).unwrap();
+        assert_eq!(probe, Admission::Probe);
+        assert_eq!(breaker.admit("fetch", later), Err(Duration::ZERO));
+        breaker.record("fetch", probe, Some(false), later);
+        assert_eq!(
+            breaker.admit("fetch", later),
+            Ok(Admission::Normal),
+            "successful probe closes the circuit"
+        );
+    }
+
+    #[test]
+    fn test_failed_probe_reopens() {
+        let breaker = breaker().minimum_calls(1);
+        let now = Instant::now();
+        let admission = breaker.admit("fetch", now).unwrap();
+        breaker.record("fetch", admission, Some(true), now);
+
+        let later = now + Duration::from_secs(10);
+        let probe = breaker.admit("fetch", later).unwrap();
+        breaker.record("fetch", probe, None, later);
+        let probe = breaker.admit("fetch", later).unwrap();
+        breaker.record("fetch", probe, Some(true), later);
+        assert!(matches!(
+            breaker.status("fetch").unwrap().state,
+            CircuitState::Open { .. }
+        ));
+        assert_eq!(breaker.status("fetch").unwrap().trips, 2);
+    }
+
+    #[test]
+    fn test_healthy_circuits_are_pruned() {
+        let breaker = breaker();
+        let now = Instant::now();
+        let admission = breaker.admit("flaky", now).unwrap();
+        breaker.record("flaky", admission, Some(true), now);
+        for index in 0..=PRUNE_THRESHOLD {
+            let tool = format!("unknown_{index}");
+            let admission = breaker.admit(&tool, now).unwrap();
+            breaker.record(&tool, admission, Some(false), now);
+        }
+
+        let circuits = breaker.circuits.lock().unwrap().len();
+        assert!(circuits < PRUNE_THRESHOLD, "{circuits} circuits tracked");
+        assert_eq!(breaker.status("flaky").unwrap().failures, 1);
+    }
+
+    #[test]
+    fn test_cancelled_calls_track_nothing() {
+        let breaker = breaker();
+        let now = Instant::now();
+        let admission = breaker.admit("fetch", now).unwrap();
+        breaker.record("fetch", admission, None, now);
+        assert!(breaker.status("fetch").is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "failure rate")]
+    fn test_rejects_zero_failure_rate() {
+        let _ = CircuitBreaker::new().failure_rate(0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "failure rate")]
+    fn test_rejects_nan_failure_rate() {
+        let _ = CircuitBreaker::new().failure_rate(f64::NAN);
+    }
+}
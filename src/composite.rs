@@ -0,0 +1,664 @@
+//! Composite providers that merge the capabilities of several providers.
+//!
+//! [`Merged`] combines two providers into one. Lists are the concatenation of both
+//! providers' lists and are paginated lazily with compound cursors, so a client can
+//! page through the union without either provider's list being loaded eagerly.
+//! Merges nest, so any number of providers can be combined with [`Merged::and`].
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use rmcp::{
+    model::{
+        CallToolRequestParams, CallToolResult, Cursor, ErrorCode, ErrorData,
+        GetPromptRequestParams, GetPromptResult, ListPromptsResult, ListResourceTemplatesResult,
+        ListResourcesResult, ListToolsResult, PaginatedRequestParams, ReadResourceRequestParams,
+        ReadResourceResult, SubscribeRequestParams, UnsubscribeRequestParams,
+    },
+    service::{RequestContext, RoleServer},
+};
+
+use crate::providers::{PromptsProvider, ResourcesProvider, ToolsProvider};
+use crate::session::SessionState;
+
+/// Error code for tools and prompts a provider does not have.
+///
+/// [`Merged`] tries the next provider when a provider answers with this code or with
+/// "method not found". Other errors, including "invalid params", reach the caller.
+pub const NOT_FOUND: ErrorCode = ErrorCode(-32004);
+
+/// Create a "not found" error.
+pub fn not_found(message: impl Into<Cow<'static, str>>) -> ErrorData {
+    ErrorData::new(NOT_FOUND, message, None)
+}
+
+/// Provider that merges two providers of the same capability.
+///
+/// - Lists return the items of the first provider followed by those of the second.
+/// - Tools and prompts are routed by name to the first provider that lists them. The
+///   names of the first provider are learned, per session, from the pages it serves,
+///   and forgotten when a listing starts over. Once the first provider's list has been
+///   read to the end, other names go to the second provider. Before that, a name the
+///   first provider has not listed is tried on it, then on the second provider if the
+///   first answers with [`NOT_FOUND`] or "method not found".
+/// - Resource requests go to the first provider, and to the second one when the first
+///   answers with "resource not found" or "method not found".
+///
+/// # Example
+///
+/// ```no_run
+/// use rmcp::model::Implementation;
+/// use rmcp_server_builder::{Merged, ServerBuilder, ToolsProvider};
+///
+/// # fn compose(search_tools: impl ToolsProvider, admin_tools: impl ToolsProvider, legacy_server: impl ToolsProvider) {
+/// let server = ServerBuilder::new()
+///     .info(Implementation::from_build_env())
+///     .tools(Merged::new(search_tools, admin_tools).and(legacy_server))
+///     .build();
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Merged<A, B> {
+    first: A,
+    second: B,
+    tools: Routes,
+    prompts: Routes,
+}
+
+impl<A, B> Merged<A, B> {
+    /// Merge two providers.
+    pub fn new(first: A, second: B) -> Self {
+        Self {
+            first,
+            second,
+            tools: Routes::default(),
+            prompts: Routes::default(),
+        }
+    }
+
+    /// Merge another provider after the ones already merged.
+    pub fn and<C>(self, next: C) -> Merged<Self, C> {
+        Merged::new(self, next)
+    }
+}
+
+// =============================================================================
+// Compound cursors
+// =============================================================================
+
+/// Position in a list concatenating several lists: the index of the list to ask, and
+/// the cursor within it.
+///
+/// Encoded as `{index}:{inner}`, or `{index}` to start a list from its beginning.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Position {
+    pub(crate) index: usize,
+    pub(crate) cursor: Option<Cursor>,
+}
+
+impl Position {
+    /// Decode a cursor into a position among `lists` lists.
+    pub(crate) fn decode(cursor: Option<&str>, lists: usize) -> Result<Self, ErrorData> {
+        let Some(cursor) = cursor else {
+            return Ok(Self {
+                index: 0,
+                cursor: None,
+            });
+        };
+        let (index, inner) = match cursor.split_once(':') {
+            Some((index, inner)) => (index, Some(inner.to_owned())),
+            None => (cursor, None),
+        };
+        match index.parse() {
+            Ok(index) if index < lists => Ok(Self {
+                index,
+                cursor: inner,
+            }),
+            _ => Err(ErrorData::invalid_params("invalid cursor", None)),
+        }
+    }
+
+    pub(crate) fn encode(&self) -> Cursor {
+        match &self.cursor {
+            Some(inner) => format!("{}:{inner}", self.index),
+            None => self.index.to_string(),
+        }
+    }
+
+    /// Build the request for the list at this position.
+    pub(crate) fn request(
+        &self,
+        request: &Option<PaginatedRequestParams>,
+    ) -> Option<PaginatedRequestParams> {
+        let meta = request.as_ref().and_then(|r| r.meta.clone());
+        if meta.is_none() && self.cursor.is_none() {
+            return None;
+        }
+        Some(PaginatedRequestParams {
+            meta,
+            cursor: self.cursor.clone(),
+        })
+    }
+
+    /// Compute the cursor following a page served from this position, given the cursor
+    /// the list returned with it.
+    pub(crate) fn next(&self, inner_next: Option<Cursor>, lists: usize) -> Option<Cursor> {
+        let next = match inner_next {
+            Some(cursor) => Position {
+                index: self.index,
+                cursor: Some(cursor),
+            },
+            None if self.index + 1 < lists => Position {
+                index: self.index + 1,
+                cursor: None,
+            },
+            None => return None,
+        };
+        Some(next.encode())
+    }
+}
+
+/// Whether an error means a provider does not handle the requested resource.
+pub(crate) fn falls_through(error: &ErrorData) -> bool {
+    error.code == ErrorCode::RESOURCE_NOT_FOUND || error.code == ErrorCode::METHOD_NOT_FOUND
+}
+
+/// Whether an error means the provider does not know the requested tool or prompt.
+fn unknown_name(error: &ErrorData) -> bool {
+    error.code == NOT_FOUND || error.code == ErrorCode::METHOD_NOT_FOUND
+}
+
+// =============================================================================
+// Routing
+// =============================================================================
+
+/// Names listed by the first provider of a merge, kept in each session's state.
+#[derive(Clone, Debug)]
+struct Routes {
+    id: u64,
+}
+
+impl Default for Routes {
+    fn default() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+}
+
+/// What a session has been shown of a first provider's list.
+#[derive(Default)]
+struct Listed {
+    names: HashSet<String>,
+    complete: bool,
+}
+
+/// Listed names of the merges used by a session, by merge.
+#[derive(Default)]
+struct SessionRoutes(HashMap<u64, Listed>);
+
+/// Which provider of a merge a named request goes to.
+enum Route {
+    First,
+    Second,
+    /// The first provider, then the second one if the first does not know the name.
+    Either,
+}
+
+impl Routes {
+    /// Record the names of a page served from `position`.
+    fn learn<'a>(
+        &self,
+        state: Option<SessionState>,
+        position: &Position,
+        names: impl Iterator<Item = &'a str>,
+        last: bool,
+    ) {
+        let Some(state) = state.filter(|_| position.index == 0) else {
+            return;
+        };
+        state.update_or_default(|routes: &mut SessionRoutes| {
+            let listed = routes.0.entry(self.id).or_default();
+            if position.cursor.is_none() {
+                *listed = Listed::default();
+            }
+            listed.names.extend(names.map(str::to_owned));
+            listed.complete = last;
+        });
+    }
+
+    fn route(&self, context: &RequestContext<RoleServer>, name: &str) -> Route {
+        let Some(state) = SessionState::from_context(context) else {
+            return Route::Either;
+        };
+        state
+            .update(|routes: &mut SessionRoutes| match routes.0.get(&self.id) {
+                Some(listed) if listed.names.contains(name) => Route::First,
+                Some(listed) if listed.complete => Route::Second,
+                _ => Route::Either,
+            })
+            .unwrap_or(Route::Either)
+    }
+}
+
+/// Send a named request to the provider `route` picks.
+async fn route<T, F, S>(route: Route, first: F, second: impl FnOnce() -> S) -> Result<T, ErrorData>
+where
+    F: Future<Output = Result<T, ErrorData>>,
+    S: Future<Output = Result<T, ErrorData>>,
+{
+    match route {
+        Route::First => first.await,
+        Route::Second => second().await,
+        Route::Either => match first.await {
+            Err(error) if unknown_name(&error) => second().await,
+            result => result,
+        },
+    }
+}
+
+// =============================================================================
+// Provider implementations
+// =============================================================================
+
+impl<A: ToolsProvider, B: ToolsProvider> ToolsProvider for Merged<A, B> {
+    async fn list_tools(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        let position = Position::decode(request.as_ref().and_then(|r| r.cursor.as_deref()), 2)?;
+        let state = SessionState::from_context(&context);
+        let result = if position.index == 1 {
+            self.second
+                .list_tools(position.request(&request), context)
+                .await?
+        } else {
+            self.first
+                .list_tools(position.request(&request), context)
+                .await?
+        };
+        self.tools.learn(
+            state,
+            &position,
+            result.tools.iter().map(|tool| tool.name.as_ref()),
+            result.next_cursor.is_none(),
+        );
+        Ok(ListToolsResult {
+            meta: result.meta,
+            next_cursor: position.next(result.next_cursor, 2),
+            tools: result.tools,
+        })
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        route(
+            self.tools.route(&context, &request.name),
+            self.first.call_tool(request.clone(), context.clone()),
+            || self.second.call_tool(request, context),
+        )
+        .await
+    }
+}
+
+impl<A: PromptsProvider, B: PromptsProvider> PromptsProvider for Merged<A, B> {
+    async fn list_prompts(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, ErrorData> {
+        let position = Position::decode(request.as_ref().and_then(|r| r.cursor.as_deref()), 2)?;
+        let state = SessionState::from_context(&context);
+        let result = if position.index == 1 {
+            self.second
+                .list_prompts(position.request(&request), context)
+                .await?
+        } else {
+            self.first
+                .list_prompts(position.request(&request), context)
+                .await?
+        };
+        self.prompts.learn(
+            state,
+            &position,
+            result.prompts.iter().map(|prompt| prompt.name.as_str()),
+            result.next_cursor.is_none(),
+        );
+        Ok(ListPromptsResult {
+            meta: result.meta,
+            next_cursor: position.next(result.next_cursor, 2),
+            prompts: result.prompts,
+        })
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, ErrorData> {
+        route(
+            self.prompts.route(&context, &request.name),
+            self.first.get_prompt(request.clone(), context.clone()),
+            || self.second.get_prompt(request, context),
+        )
+        .await
+    }
+}
+
+impl<A: ResourcesProvider, B: ResourcesProvider> ResourcesProvider for Merged<A, B> {
+    async fn list_resources(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, ErrorData> {
+        let position = Position::decode(request.as_ref().and_then(|r| r.cursor.as_deref()), 2)?;
+        let result = if position.index == 1 {
+            self.second
+                .list_resources(position.request(&request), context)
+                .await?
+        } else {
+            self.first
+                .list_resources(position.request(&request), context)
+                .await?
+        };
+        Ok(ListResourcesResult {
+            meta: result.meta,
+            next_cursor: position.next(result.next_cursor, 2),
+            resources: result.resources,
+        })
+    }
+
+    async fn list_resource_templates(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, ErrorData> {
+        let position = Position::decode(request.as_ref().and_then(|r| r.cursor.as_deref()), 2)?;
+        let result = if position.index == 1 {
+            self.second
+                .list_resource_templates(position.request(&request), context)
+                .await?
+        } else {
+            self.first
+                .list_resource_templates(position.request(&request), context)
+                .await?
+        };
+        Ok(ListResourceTemplatesResult {
+            meta: result.meta,
+            next_cursor: position.next(result.next_cursor, 2),
+            resource_templates: result.resource_templates,
+        })
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, ErrorData> {
+        match self
+            .first
+            .read_resource(request.clone(), context.clone())
+            .await
+        {
+            Err(error) if falls_through(&error) => {
+                self.second.read_resource(request, context).await
+            }
+            result => result,
+        }
+    }
+
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        match self.first.subscribe(request.clone(), context.clone()).await {
+            Err(error) if falls_through(&error) => self.second.subscribe(request, context).await,
+            result => result,
+        }
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        match self
+            .first
+            .unsubscribe(request.clone(), context.clone())
+            .await
+        {
+            Err(error) if falls_through(&error) => self.second.unsubscribe(request, context).await,
+            result => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_walks_both_providers() {
+        let start = Position::decode(None, 2).unwrap();
+        let next = start.next(Some("abc".into()), 2).unwrap();
+        assert_eq!(next, "0:abc");
+
+        let position = Position::decode(Some(&next), 2).unwrap();
+        assert_eq!(position.cursor.as_deref(), Some("abc"));
+        let next = position.next(None, 2).unwrap();
+        assert_eq!(next, "1");
+
+        let position = Position::decode(Some(&next), 2).unwrap();
+        assert_eq!(position.index, 1);
+        assert_eq!(position.cursor, None);
+        assert_eq!(position.next(None, 2), None);
+    }
+
+    #[test]
+    fn test_nested_cursor_is_preserved() {
+        let position = Position::decode(Some("0:1:xyz"), 2).unwrap();
+        assert_eq!(position.index, 0);
+        assert_eq!(position.cursor.as_deref(), Some("1:xyz"));
+        assert_eq!(position.encode(), "0:1:xyz");
+    }
+
+    #[test]
+    fn test_rejects_unknown_index() {
+        assert!(Position::decode(Some("2:abc"), 2).is_err());
+        assert!(Position::decode(Some("x"), 2).is_err());
+    }
+
+    mod session {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        use rmcp::model::{Content, Implementation, Tool};
+        use rmcp::service::{RoleClient, RunningService, ServiceError};
+
+        use super::*;
+        use crate::{ServerBuilder, test_support};
+
+        /// Tools provider serving `names` two at a time.
+        struct Catalogue {
+            label: &'static str,
+            names: Vec<&'static str>,
+            lists: Arc<AtomicUsize>,
+        }
+
+        impl Catalogue {
+            fn new(label: &'static str, names: &[&'static str]) -> Self {
+                Self {
+                    label,
+                    names: names.to_vec(),
+                    lists: Arc::default(),
+                }
+            }
+        }
+
+        impl ToolsProvider for Catalogue {
+            async fn list_tools(
+                &self,
+                request: Option<PaginatedRequestParams>,
+                _context: RequestContext<RoleServer>,
+            ) -> Result<ListToolsResult, ErrorData> {
+                self.lists.fetch_add(1, Ordering::Relaxed);
+                let start = request
+                    .and_then(|r| r.cursor)
+                    .map_or(0, |cursor| cursor.parse().unwrap());
+                let end = self.names.len().min(start + 2);
+                Ok(ListToolsResult {
+                    meta: None,
+                    next_cursor: (end < self.names.len()).then(|| end.to_string()),
+                    tools: self.names[start..end]
+                        .iter()
+                        .map(|name| Tool::new(*name, "", Arc::default()))
+                        .collect(),
+                })
+            }
+
+            async fn call_tool(
+                &self,
+                request: CallToolRequestParams,
+                _context: RequestContext<RoleServer>,
+            ) -> Result<CallToolResult, ErrorData> {
+                match request.name.as_ref() {
+                    "broken" => Err(ErrorData::invalid_params(
+                        format!("{}: bad arguments", self.label),
+                        None,
+                    )),
+                    name if self.names.contains(&name) => {
+                        Ok(CallToolResult::success(vec![Content::text(format!(
+                            "{}:{name}",
+                            self.label
+                        ))]))
+                    }
+                    _ => Err(not_found("tool not found")),
+                }
+            }
+        }
+
+        async fn connect(tools: impl ToolsProvider) -> RunningService<RoleClient, ()> {
+            let server = ServerBuilder::new()
+                .info(Implementation::default())
+                .tools(tools)
+                .build();
+            test_support::connect(server, ()).await
+        }
+
+        async fn call(
+            client: &RunningService<RoleClient, ()>,
+            name: &'static str,
+        ) -> Result<String, ServiceError> {
+            let result = client.call_tool(test_support::call(name)).await?;
+            Ok(result.content[0].as_text().unwrap().text.clone())
+        }
+
+        #[tokio::test]
+        async fn test_lists_concatenate_across_pages() {
+            let client = connect(
+                Merged::new(
+                    Catalogue::new("a", &["a1", "a2", "a3"]),
+                    Catalogue::new("b", &["b1", "b2"]),
+                )
+                .and(Catalogue::new("c", &["c1"])),
+            )
+            .await;
+
+            let first = client.list_tools(None).await.unwrap();
+            let names = |result: &ListToolsResult| {
+                result
+                    .tools
+                    .iter()
+                    .map(|tool| tool.name.to_string())
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(names(&first), ["a1", "a2"]);
+            let second = client
+                .list_tools(Some(PaginatedRequestParams {
+                    meta: None,
+                    cursor: first.next_cursor,
+                }))
+                .await
+                .unwrap();
+            assert_eq!(names(&second), ["a3"]);
+
+            let all: Vec<_> = client
+                .list_all_tools()
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|tool| tool.name.to_string())
+                .collect();
+            assert_eq!(all, ["a1", "a2", "a3", "b1", "b2", "c1"]);
+        }
+
+        #[tokio::test]
+        async fn test_routes_without_listing() {
+            let first = Catalogue::new("a", &["a1", "shared"]);
+            let lists = first.lists.clone();
+            let client = connect(
+                Merged::new(first, Catalogue::new("b", &["b1", "shared"]))
+                    .and(Catalogue::new("c", &["c1"])),
+            )
+            .await;
+
+            assert_eq!(call(&client, "a1").await.unwrap(), "a:a1");
+            assert_eq!(call(&client, "b1").await.unwrap(), "b:b1");
+            assert_eq!(call(&client, "c1").await.unwrap(), "c:c1");
+            assert_eq!(call(&client, "shared").await.unwrap(), "a:shared");
+            assert!(call(&client, "missing").await.is_err());
+            assert_eq!(lists.load(Ordering::Relaxed), 0);
+        }
+
+        #[tokio::test]
+        async fn test_listed_names_stay_on_first_provider() {
+            let client = connect(Merged::new(
+                Catalogue::new("a", &["a1", "broken"]),
+                Catalogue::new("b", &["b1", "broken"]),
+            ))
+            .await;
+
+            let error = |result: Result<String, ServiceError>| match result {
+                Err(ServiceError::McpError(error)) => error,
+                result => panic!("unexpected result: {result:?}"),
+            };
+            // "Invalid params" is an error of the tool, not an unknown name.
+            let before = error(call(&client, "broken").await);
+            assert_eq!(before.code, ErrorCode::INVALID_PARAMS);
+            assert_eq!(before.message, "a: bad arguments");
+            client.list_all_tools().await.unwrap();
+            let after = error(call(&client, "broken").await);
+            assert_eq!(after.code, ErrorCode::INVALID_PARAMS);
+            assert_eq!(after.message, "a: bad arguments");
+            assert_eq!(call(&client, "b1").await.unwrap(), "b:b1");
+        }
+
+        #[tokio::test]
+        async fn test_unlisted_names_skip_first_provider_after_full_listing() {
+            let first = Catalogue::new("a", &["a1", "a2", "a3"]);
+            let lists = first.lists.clone();
+            let client = connect(Merged::new(first, Catalogue::new("b", &["b1"]))).await;
+            let cursor = |result: ListToolsResult| PaginatedRequestParams {
+                meta: None,
+                cursor: result.next_cursor,
+            };
+
+            // The first provider's list is only partly read: unknown names still try it.
+            let page = client.list_tools(None).await.unwrap();
+            assert!(call(&client, "missing").await.is_err());
+            let page = client.list_tools(Some(cursor(page))).await.unwrap();
+            assert_eq!(call(&client, "a3").await.unwrap(), "a:a3");
+            assert_eq!(lists.load(Ordering::Relaxed), 2);
+            // The next page comes from the second provider.
+            client.list_tools(Some(cursor(page))).await.unwrap();
+            assert_eq!(call(&client, "b1").await.unwrap(), "b:b1");
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//! Requests for structured input from the user.
+//!
+//! [`Elicitation`] asks the client to collect values matching an [`ElicitationSchema`]
+//! from the user, from any provider method holding a [`RequestContext`], and returns
+//! the user's answer as an [`ElicitationOutcome`] with the values parsed into a typed
+//! response.
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use rmcp::{
+    model::{
+        ClientResult, CreateElicitationRequest, CreateElicitationRequestParams, ElicitationAction,
+        ElicitationSchema, ErrorData, ServerRequest,
+    },
+    service::{RequestContext, RoleServer},
+};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::sampling::request_client;
+use crate::session::ConnectedClient;
+
+/// Answer of the user to an elicitation request.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ElicitationOutcome<T> {
+    /// The user submitted the requested values.
+    Accepted(T),
+    /// The user explicitly declined to provide the values.
+    Declined,
+    /// The user dismissed the request without choosing.
+    Cancelled,
+    /// The client does not support elicitation, so the user was not asked.
+    Unsupported,
+}
+
+impl<T> ElicitationOutcome<T> {
+    /// Get the submitted values, if the user accepted.
+    pub fn accepted(self) -> Option<T> {
+        match self {
+            Self::Accepted(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// Request for structured input from the user, parsed into a `T`.
+///
+/// The schema describes a flat object of primitive properties; it can be built with
+/// [`ElicitationSchema::builder`], or derived from `T` with rmcp's
+/// `ElicitationSchema::from_type` when its `schemars` feature is enabled.
+///
+/// # Example
+///
+/// ```no_run
+/// use rmcp::model::{CallToolResult, Content, ElicitationSchema, ErrorData};
+/// use rmcp::service::{RequestContext, RoleServer};
+/// use rmcp_server_builder::{Elicitation, ElicitationOutcome};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Confirmation {
+///     confirm: bool,
+/// }
+///
+/// # async fn delete_branch() -> Result<CallToolResult, ErrorData> { Ok(CallToolResult::success(vec![])) }
+/// # async fn call_tool(force: bool, context: RequestContext<RoleServer>) -> Result<CallToolResult, ErrorData> {
+/// // In a tools provider's `call_tool`:
+/// let schema = ElicitationSchema::builder()
+///     .required_bool("confirm")
+///     .build()
+///     .map_err(|error| ErrorData::internal_error(error, None))?;
+/// match Elicitation::<Confirmation>::new("Delete the branch?", schema)
+///     .send(&context)
+///     .await?
+/// {
+///     ElicitationOutcome::Accepted(Confirmation { confirm: true }) => delete_branch().await,
+///     // Without elicitation support, fall back to requiring a `force` argument.
+///     ElicitationOutcome::Unsupported if force => delete_branch().await,
+///     _ => Ok(CallToolResult::error(vec![Content::text("not deleted")])),
+/// }
+/// # }
+/// ```
+pub struct Elicitation<T> {
+    message: String,
+    schema: ElicitationSchema,
+    timeout: Option<Duration>,
+    response: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> Elicitation<T> {
+    /// Ask the user for the values described by `schema`, explaining why with `message`.
+    pub fn new(message: impl Into<String>, schema: ElicitationSchema) -> Self {
+        Self {
+            message: message.into(),
+            schema,
+            timeout: None,
+            response: PhantomData,
+        }
+    }
+
+    /// Set how long to wait for the user to answer.
+    ///
+    /// By default, the request waits until the user answers or the request of the
+    /// context is cancelled.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Send the request to the client of the session of `context`.
+    ///
+    /// Returns [`ElicitationOutcome::Unsupported`] without sending anything when the
+    /// client does not support elicitation. Fails with an internal error when the
+    /// client does not answer before the timeout, the request of `context` is cancelled
+    /// first, or the submitted values are not a valid `T`. Errors returned by the client
+    /// are passed through.
+    pub async fn send(
+        self,
+        context: &RequestContext<RoleServer>,
+    ) -> Result<ElicitationOutcome<T>, ErrorData> {
+        if !ConnectedClient::from_context(context)
+            .is_some_and(|client| client.supports_elicitation())
+        {
+            return Ok(ElicitationOutcome::Unsupported);
+        }
+
+        let request = ServerRequest::CreateElicitationRequest(CreateElicitationRequest {
+            method: Default::default(),
+            params: CreateElicitationRequestParams::FormElicitationParams {
+                meta: None,
+                message: self.message,
+                requested_schema: self.schema,
+            },
+            extensions: Default::default(),
+        });
+        let result = match request_client(context, request, self.timeout, "elicitation").await? {
+            ClientResult::CreateElicitationResult(result) => result,
+            _ => {
+                return Err(ErrorData::internal_error(
+                    "unexpected response to elicitation request",
+                    None,
+                ));
+            }
+        };
+        match result.action {
+            ElicitationAction::Accept => {
+                let content = result
+                    .content
+                    .unwrap_or_else(|| Value::Object(Default::default()));
+                serde_json::from_value(content)
+                    .map(ElicitationOutcome::Accepted)
+                    .map_err(|error| {
+                        ErrorData::internal_error(
+                            format!("invalid elicited content: {error}"),
+                            None,
+                        )
+                    })
+            }
+            ElicitationAction::Decline => Ok(ElicitationOutcome::Declined),
+            ElicitationAction::Cancel => Ok(ElicitationOutcome::Cancelled),
+        }
+    }
+}
+
+impl<T> Clone for Elicitation<T> {
+    fn clone(&self) -> Self {
+        Self {
+            message: self.message.clone(),
+            schema: self.schema.clone(),
+            timeout: self.timeout,
+            response: PhantomData,
+        }
+    }
+}
+
+impl<T> fmt::Debug for Elicitation<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Elicitation")
+            .field("message", &self.message)
+            .field("schema", &self.schema)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use rmcp::handler::client::ClientHandler;
+    use rmcp::model::{
+        CallToolRequestParams, CallToolResult, ClientCapabilities, ClientInfo, Content,
+        CreateElicitationResult, Implementation, ListToolsResult, PaginatedRequestParams,
+    };
+    use rmcp::service::RoleClient;
+    use serde_json::json;
+
+    use super::*;
+    use crate::providers::ToolsProvider;
+    use crate::{ServerBuilder, test_support};
+
+    struct Deleter;
+
+    impl ToolsProvider for Deleter {
+        async fn list_tools(
+            &self,
+            _request: Option<PaginatedRequestParams>,
+            _context: RequestContext<RoleServer>,
+        ) -> Result<ListToolsResult, ErrorData> {
+            Ok(ListToolsResult::default())
+        }
+
+        async fn call_tool(
+            &self,
+            _request: CallToolRequestParams,
+            context: RequestContext<RoleServer>,
+        ) -> Result<CallToolResult, ErrorData> {
+            let schema = ElicitationSchema::builder()
+                .required_bool("confirm")
+                .build()
+                .unwrap();
+            let outcome = Elicitation::<BTreeMap<String, bool>>::new("Delete?", schema)
+                .send(&context)
+                .await?;
+            Ok(CallToolResult::success(vec![Content::text(format!(
+                "{outcome:?}"
+            ))]))
+        }
+    }
+
+    struct User(ElicitationAction);
+
+    impl ClientHandler for User {
+        async fn create_elicitation(
+            &self,
+            request: CreateElicitationRequestParams,
+            _context: RequestContext<RoleClient>,
+        ) -> Result<CreateElicitationResult, ErrorData> {
+            let CreateElicitationRequestParams::FormElicitationParams { message, .. } = request
+            else {
+                panic!("expected a form elicitation");
+            };
+            assert_eq!(message, "Delete?");
+            Ok(CreateElicitationResult {
+                action: self.0.clone(),
+                content: (self.0 == ElicitationAction::Accept).then(|| json!({ "confirm": true })),
+            })
+        }
+
+        fn get_info(&self) -> ClientInfo {
+            ClientInfo {
+                capabilities: ClientCapabilities::builder().enable_elicitation().build(),
+                ..Default::default()
+            }
+        }
+    }
+
+    async fn call<C: ClientHandler>(client: C) -> String {
+        let server = ServerBuilder::new()
+            .info(Implementation::default())
+            .tools(Deleter)
+            .build();
+        let client = test_support::connect(server, client).await;
+        let result = client
+            .call_tool(test_support::call("delete"))
+            .await
+            .unwrap();
+        result.content[0].as_text().unwrap().text.clone()
+    }
+
+    #[tokio::test]
+    async fn test_elicitation() {
+        assert_eq!(
+            call(User(ElicitationAction::Accept)).await,
+            "Accepted({\"confirm\": true})"
+        );
+        assert_eq!(call(User(ElicitationAction::Decline)).await, "Declined");
+        assert_eq!(call(User(ElicitationAction::Cancel)).await, "Cancelled");
+        assert_eq!(call(()).await, "Unsupported");
+    }
+}
@@ -0,0 +1,265 @@
+//! Per-session server instances sharing their providers.
+//!
+//! Transports hosting many sessions, such as streamable HTTP, need one server value per
+//! session. A [`ServerFactory`] creates them: either by cloning a server whose
+//! providers are [`Shared`], as built by
+//! [`ServerBuilder::build_factory`](crate::ServerBuilder::build_factory), or by calling
+//! a closure that composes a fresh server for each session.
+
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use rmcp::{
+    model::{
+        CallToolRequestParams, CallToolResult, CompleteRequestParams, CompleteResult, ErrorData,
+        GetPromptRequestParams, GetPromptResult, ListPromptsResult, ListResourceTemplatesResult,
+        ListResourcesResult, ListToolsResult, PaginatedRequestParams, ReadResourceRequestParams,
+        ReadResourceResult, ServerCapabilities, ServerInfo, SetLevelRequestParams,
+        SubscribeRequestParams, UnsubscribeRequestParams,
+    },
+    service::{RequestContext, RoleServer},
+};
+
+use crate::providers::{
+    CompletionProvider, LoggingProvider, PromptsProvider, ResourcesProvider, ServerInfoProvider,
+    ToolsProvider,
+};
+use crate::server::Server;
+
+/// Provider shared between servers through an [`Arc`].
+///
+/// Cloning a shared provider only clones the reference, so expensive providers can be
+/// used by the servers of many sessions at once.
+pub struct Shared<T>(Arc<T>);
+
+impl<T> Shared<T> {
+    /// Share a provider.
+    pub fn new(provider: T) -> Self {
+        Self(Arc::new(provider))
+    }
+}
+
+impl<T> From<Arc<T>> for Shared<T> {
+    fn from(provider: Arc<T>) -> Self {
+        Self(provider)
+    }
+}
+
+impl<T> Clone for Shared<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> Deref for Shared<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Shared<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Shared").field(&self.0).finish()
+    }
+}
+
+/// Server whose providers are all [`Shared`], as created by
+/// [`ServerBuilder::build_factory`](crate::ServerBuilder::build_factory).
+pub type SharedServer<T, P, R, C, L, I> =
+    Server<Shared<T>, Shared<P>, Shared<R>, Shared<C>, Shared<L>, Shared<I>>;
+
+/// Creates a server for every new session.
+///
+/// # Example
+///
+/// ```no_run
+/// use rmcp::model::Implementation;
+/// use rmcp_server_builder::{Merged, ServerBuilder, ServerFactory, Shared, ToolsProvider};
+///
+/// # fn compose<Index, Scratchpad>(info: Implementation, search_index: Index)
+/// # where
+/// #     Index: ToolsProvider + Clone,
+/// #     Scratchpad: ToolsProvider + Default,
+/// # {
+/// // Every session clones the same providers.
+/// let factory = ServerBuilder::new()
+///     .info(info.clone())
+///     .tools(search_index.clone())
+///     .build_factory();
+///
+/// // Every session gets its own scratchpad, next to the shared search index.
+/// let index = Shared::new(search_index);
+/// let factory = ServerFactory::new(move || {
+///     ServerBuilder::new()
+///         .info(info.clone())
+///         .tools(Merged::new(index.clone(), Scratchpad::default()))
+///         .build()
+/// });
+/// # }
+/// ```
+pub struct ServerFactory<S> {
+    create: Arc<dyn Fn() -> S + Send + Sync>,
+}
+
+impl<S> ServerFactory<S> {
+    /// Create servers by calling `create`.
+    pub fn new(create: impl Fn() -> S + Send + Sync + 'static) -> Self {
+        Self {
+            create: Arc::new(create),
+        }
+    }
+
+    /// Create a server for a new session.
+    pub fn create(&self) -> S {
+        (self.create)()
+    }
+}
+
+impl<S> Clone for ServerFactory<S> {
+    fn clone(&self) -> Self {
+        Self {
+            create: self.create.clone(),
+        }
+    }
+}
+
+impl<S> fmt::Debug for ServerFactory<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ServerFactory").finish_non_exhaustive()
+    }
+}
+
+// =============================================================================
+// Provider implementations
+// =============================================================================
+
+impl<T: ToolsProvider> ToolsProvider for Shared<T> {
+    async fn list_tools(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        self.0.list_tools(request, context).await
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.0.call_tool(request, context).await
+    }
+}
+
+impl<P: PromptsProvider> PromptsProvider for Shared<P> {
+    async fn list_prompts(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, ErrorData> {
+        self.0.list_prompts(request, context).await
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, ErrorData> {
+        self.0.get_prompt(request, context).await
+    }
+}
+
+impl<R: ResourcesProvider> ResourcesProvider for Shared<R> {
+    async fn list_resources(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, ErrorData> {
+        self.0.list_resources(request, context).await
+    }
+
+    async fn list_resource_templates(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, ErrorData> {
+        self.0.list_resource_templates(request, context).await
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, ErrorData> {
+        self.0.read_resource(request, context).await
+    }
+
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        self.0.subscribe(request, context).await
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        self.0.unsubscribe(request, context).await
+    }
+}
+
+impl<C: CompletionProvider> CompletionProvider for Shared<C> {
+    async fn complete(
+        &self,
+        request: CompleteRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CompleteResult, ErrorData> {
+        self.0.complete(request, context).await
+    }
+}
+
+impl<L: LoggingProvider> LoggingProvider for Shared<L> {
+    async fn set_level(
+        &self,
+        request: SetLevelRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        self.0.set_level(request, context).await
+    }
+}
+
+impl<I: ServerInfoProvider> ServerInfoProvider for Shared<I> {
+    fn get_info(&self) -> ServerInfo {
+        self.0.get_info()
+    }
+
+    fn capabilities(&self) -> ServerCapabilities {
+        self.0.capabilities()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rmcp::model::Implementation;
+
+    use crate::ServerBuilder;
+
+    #[test]
+    fn test_build_factory() {
+        let factory = ServerBuilder::new()
+            .info(Implementation::from_build_env())
+            .build_factory();
+        let first = factory.create();
+        let second = factory.create();
+
+        assert_ne!(first.session_id, second.session_id);
+        assert!(std::ptr::eq(&*first.info, &*second.info));
+        assert!(first.tools.is_none());
+    }
+}
@@ -0,0 +1,416 @@
+//! Tool filtering with allow and deny lists.
+//!
+//! [`FilteredTools`] wraps a tools provider and exposes only the tools accepted by a
+//! [`ToolFilter`]. Hidden tools are omitted from `list_tools` and `call_tool` rejects
+//! them as if they did not exist.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use rmcp::{
+    model::{
+        CallToolRequestParams, CallToolResult, ErrorData, ListToolsResult, PaginatedRequestParams,
+        Tool,
+    },
+    service::{RequestContext, RoleServer},
+};
+
+use crate::pagination::fetch_all;
+use crate::providers::ToolsProvider;
+
+/// A single rule matching tools.
+#[derive(Clone)]
+enum Matcher {
+    Name(String),
+    Glob(String),
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+    Predicate(Arc<dyn Fn(&Tool) -> bool + Send + Sync>),
+}
+
+impl Matcher {
+    /// Match a tool by name, using its definition for predicates.
+    ///
+    /// Returns `None` for a predicate when the definition is not available.
+    fn matches(&self, name: &str, tool: Option<&Tool>) -> Option<bool> {
+        match self {
+            Matcher::Name(expected) => Some(name == expected),
+            Matcher::Glob(pattern) => Some(glob_match(pattern, name)),
+            #[cfg(feature = "regex")]
+            Matcher::Regex(regex) => Some(regex.is_match(name)),
+            Matcher::Predicate(predicate) => tool.map(|tool| predicate(tool)),
+        }
+    }
+
+    fn needs_metadata(&self) -> bool {
+        matches!(self, Matcher::Predicate(_))
+    }
+}
+
+impl fmt::Debug for Matcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Matcher::Name(name) => f.debug_tuple("Name").field(name).finish(),
+            Matcher::Glob(pattern) => f.debug_tuple("Glob").field(pattern).finish(),
+            #[cfg(feature = "regex")]
+            Matcher::Regex(regex) => f.debug_tuple("Regex").field(&regex.as_str()).finish(),
+            Matcher::Predicate(_) => f.write_str("Predicate"),
+        }
+    }
+}
+
+/// Allow and deny rules deciding which tools are exposed.
+///
+/// A tool is exposed when it matches at least one allow rule (or no allow rule is
+/// configured) and matches no deny rule. Rules match tools by exact name, by glob
+/// pattern (`*` and `?` wildcards), by regular expression (with the `regex` feature),
+/// or by an arbitrary predicate such as one inspecting the tool's annotations.
+///
+/// # Example
+///
+/// ```
+/// use rmcp_server_builder::ToolFilter;
+///
+/// let filter = ToolFilter::new()
+///     .allow_glob("repo_*")
+///     .deny_name("repo_delete")
+///     .deny_if(|tool| tool.annotations.as_ref().is_some_and(|a| a.is_destructive()));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ToolFilter {
+    allow: Vec<Matcher>,
+    deny: Vec<Matcher>,
+}
+
+impl ToolFilter {
+    /// Create a filter that exposes every tool.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow the tool with this exact name.
+    pub fn allow_name(mut self, name: impl Into<String>) -> Self {
+        self.allow.push(Matcher::Name(name.into()));
+        self
+    }
+
+    /// Allow tools whose name matches a glob pattern.
+    pub fn allow_glob(mut self, pattern: impl Into<String>) -> Self {
+        self.allow.push(Matcher::Glob(pattern.into()));
+        self
+    }
+
+    /// Allow tools whose name matches a regular expression.
+    #[cfg(feature = "regex")]
+    pub fn allow_regex(mut self, regex: regex::Regex) -> Self {
+        self.allow.push(Matcher::Regex(regex));
+        self
+    }
+
+    /// Allow tools accepted by a predicate.
+    pub fn allow_if(mut self, predicate: impl Fn(&Tool) -> bool + Send + Sync + 'static) -> Self {
+        self.allow.push(Matcher::Predicate(Arc::new(predicate)));
+        self
+    }
+
+    /// Deny the tool with this exact name.
+    pub fn deny_name(mut self, name: impl Into<String>) -> Self {
+        self.deny.push(Matcher::Name(name.into()));
+        self
+    }
+
+    /// Deny tools whose name matches a glob pattern.
+    pub fn deny_glob(mut self, pattern: impl Into<String>) -> Self {
+        self.deny.push(Matcher::Glob(pattern.into()));
+        self
+    }
+
+    /// Deny tools whose name matches a regular expression.
+    #[cfg(feature = "regex")]
+    pub fn deny_regex(mut self, regex: regex::Regex) -> Self {
+        self.deny.push(Matcher::Regex(regex));
+        self
+    }
+
+    /// Deny tools accepted by a predicate.
+    pub fn deny_if(mut self, predicate: impl Fn(&Tool) -> bool + Send + Sync + 'static) -> Self {
+        self.deny.push(Matcher::Predicate(Arc::new(predicate)));
+        self
+    }
+
+    /// Check whether a tool is exposed by this filter.
+    pub fn is_allowed(&self, tool: &Tool) -> bool {
+        self.evaluate(&tool.name, Some(tool)) == Some(true)
+    }
+
+    /// Decide whether a tool is exposed, or return `None` when the decision depends on
+    /// a predicate and the tool's definition is not available.
+    fn evaluate(&self, name: &str, tool: Option<&Tool>) -> Option<bool> {
+        let any = |rules: &[Matcher]| {
+            let mut undecided = false;
+            for rule in rules {
+                match rule.matches(name, tool) {
+                    Some(true) => return Some(true),
+                    Some(false) => {}
+                    None => undecided = true,
+                }
+            }
+            (!undecided).then_some(false)
+        };
+        let allowed = if self.allow.is_empty() {
+            Some(true)
+        } else {
+            any(&self.allow)
+        };
+        match (allowed, any(&self.deny)) {
+            (Some(false), _) | (_, Some(true)) => Some(false),
+            (Some(true), Some(false)) => Some(true),
+            _ => None,
+        }
+    }
+
+    /// Whether rules need more than the tool name to be evaluated.
+    fn needs_metadata(&self) -> bool {
+        self.allow
+            .iter()
+            .chain(&self.deny)
+            .any(Matcher::needs_metadata)
+    }
+}
+
+/// Match `text` against a glob pattern supporting `*` and `?`.
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Tools provider exposing only the tools accepted by a [`ToolFilter`].
+///
+/// Calls are checked against name rules directly. When a predicate decides, the tool
+/// definitions the inner provider listed are reused; they are kept from one listing
+/// that starts without a cursor to the next, and the inner list is fetched only when
+/// no listing has completed yet.
+///
+/// Usually configured through [`ServerBuilder::filter_tools`](crate::ServerBuilder::filter_tools).
+#[derive(Clone, Debug)]
+pub struct FilteredTools<T> {
+    inner: T,
+    filter: ToolFilter,
+    resolved: Arc<Mutex<Resolved>>,
+}
+
+/// Tool definitions of the inner provider, as of its latest listing.
+#[derive(Debug, Default)]
+struct Resolved {
+    tools: HashMap<String, Tool>,
+    complete: bool,
+}
+
+impl Resolved {
+    fn record(&mut self, restart: bool, tools: &[Tool], last_page: bool) {
+        if restart {
+            self.tools.clear();
+            self.complete = false;
+        }
+        self.tools.extend(
+            tools
+                .iter()
+                .map(|tool| (tool.name.to_string(), tool.clone())),
+        );
+        self.complete |= last_page;
+    }
+}
+
+impl<T> FilteredTools<T> {
+    /// Wrap a tools provider with a filter.
+    pub fn new(inner: T, filter: ToolFilter) -> Self {
+        Self {
+            inner,
+            filter,
+            resolved: Arc::default(),
+        }
+    }
+
+    /// Get a reference to the wrapped provider.
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: ToolsProvider> FilteredTools<T> {
+    /// Decide on a tool from its listed definition, fetching the inner list if needed.
+    async fn resolve(
+        &self,
+        request: &CallToolRequestParams,
+        context: &RequestContext<RoleServer>,
+    ) -> Result<bool, ErrorData> {
+        {
+            let resolved = self.resolved.lock().unwrap();
+            if let Some(tool) = resolved.tools.get(request.name.as_ref()) {
+                return Ok(self.filter.is_allowed(tool));
+            }
+            if resolved.complete {
+                return Ok(false);
+            }
+        }
+
+        let tools = fetch_all(request.meta.clone(), |params| {
+            let context = context.clone();
+            async move {
+                let result = self.inner.list_tools(Some(params), context).await?;
+                Ok((result.tools, result.next_cursor))
+            }
+        })
+        .await?;
+        let allowed = tools
+            .iter()
+            .find(|tool| tool.name == request.name)
+            .is_some_and(|tool| self.filter.is_allowed(tool));
+        self.resolved.lock().unwrap().record(true, &tools, true);
+        Ok(allowed)
+    }
+}
+
+fn tool_not_available(name: &str) -> ErrorData {
+    ErrorData::invalid_params(format!("tool '{name}' is not available"), None)
+}
+
+impl<T: ToolsProvider> ToolsProvider for FilteredTools<T> {
+    async fn list_tools(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        let restart = request.as_ref().is_none_or(|r| r.cursor.is_none());
+        let mut result = self.inner.list_tools(request, context).await?;
+        if self.filter.needs_metadata() {
+            self.resolved.lock().unwrap().record(
+                restart,
+                &result.tools,
+                result.next_cursor.is_none(),
+            );
+        }
+        result.tools.retain(|tool| self.filter.is_allowed(tool));
+        Ok(result)
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let allowed = match self.filter.evaluate(&request.name, None) {
+            Some(allowed) => allowed,
+            None => self.resolve(&request, &context).await?,
+        };
+
+        if !allowed {
+            return Err(tool_not_available(&request.name));
+        }
+        self.inner.call_tool(request, context).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::ToolAnnotations;
+
+    fn tool(name: &'static str) -> Tool {
+        Tool::new(name, "", Arc::default())
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("repo_*", "repo_list"));
+        assert!(glob_match("*_delete", "repo_delete"));
+        assert!(glob_match("repo_?et", "repo_get"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("repo_*", "issue_list"));
+        assert!(!glob_match("repo_?", "repo_get"));
+    }
+
+    #[test]
+    fn test_allow_and_deny() {
+        let filter = ToolFilter::new()
+            .allow_glob("repo_*")
+            .deny_name("repo_delete");
+        assert!(filter.is_allowed(&tool("repo_list")));
+        assert!(!filter.is_allowed(&tool("repo_delete")));
+        assert!(!filter.is_allowed(&tool("issue_list")));
+        assert!(ToolFilter::new().is_allowed(&tool("anything")));
+    }
+
+    #[test]
+    fn test_predicate_on_annotations() {
+        let filter = ToolFilter::new().allow_if(|tool| {
+            tool.annotations.as_ref().and_then(|a| a.read_only_hint) == Some(true)
+        });
+        assert!(filter.needs_metadata());
+        assert!(filter.is_allowed(&tool("read").annotate(ToolAnnotations::new().read_only(true))));
+        assert!(!filter.is_allowed(&tool("write")));
+    }
+
+    #[test]
+    fn test_name_rules_decide_without_metadata() {
+        let filter = ToolFilter::new()
+            .allow_glob("repo_*")
+            .deny_name("repo_delete")
+            .deny_if(|tool| tool.annotations.is_none());
+        assert_eq!(filter.evaluate("issue_list", None), Some(false));
+        assert_eq!(filter.evaluate("repo_delete", None), Some(false));
+        assert_eq!(filter.evaluate("repo_list", None), None);
+
+        let filter = ToolFilter::new()
+            .allow_name("ping")
+            .allow_if(|tool| tool.annotations.is_some());
+        assert_eq!(filter.evaluate("ping", None), Some(true));
+        assert_eq!(filter.evaluate("other", None), None);
+    }
+
+    #[test]
+    fn test_resolved_restarts_with_listing() {
+        let mut resolved = Resolved::default();
+        resolved.record(true, &[tool("a"), tool("b")], false);
+        resolved.record(false, &[tool("c")], true);
+        assert!(resolved.complete);
+        assert_eq!(resolved.tools.len(), 3);
+
+        resolved.record(true, &[tool("d")], false);
+        assert!(!resolved.complete);
+        assert_eq!(resolved.tools.keys().collect::<Vec<_>>(), ["d"]);
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_deny_regex() {
+        let filter = ToolFilter::new().deny_regex(regex::Regex::new("^admin_").unwrap());
+        assert!(!filter.is_allowed(&tool("admin_reset")));
+        assert!(filter.is_allowed(&tool("user_list")));
+    }
+}
@@ -0,0 +1,1127 @@
+//! Gateway mounting several remote MCP servers behind one composed server.
+//!
+//! A [`Gateway`] holds any number of downstream servers, each mounted under a
+//! namespace. Tools and prompts are exposed as `{namespace}__{name}`, resources keep
+//! their URIs. Downstream servers are connected on first use and reconnected with
+//! backoff after their session closes or fails a health check.
+
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use rmcp::{
+    RoleClient,
+    model::{
+        CallToolRequestParams, CallToolResult, CompleteRequestParams, CompleteResult, Cursor,
+        ErrorCode, ErrorData, GetPromptRequestParams, GetPromptResult, ListPromptsResult,
+        ListResourceTemplatesResult, ListResourcesResult, ListToolsResult, PaginatedRequestParams,
+        ReadResourceRequestParams, ReadResourceResult, Reference, SubscribeRequestParams,
+        UnsubscribeRequestParams,
+    },
+    service::{RequestContext, RoleServer},
+    transport::IntoTransport,
+};
+use serde_json::json;
+use tokio::task::JoinHandle;
+
+use crate::composite::{Position, falls_through};
+use crate::providers::{CompletionProvider, PromptsProvider, ResourcesProvider, ToolsProvider};
+use crate::proxy::{ProxyProvider, Upstream};
+use crate::retry::Backoff;
+
+/// Separator between namespaces and tool or prompt names, unless configured otherwise.
+pub const DEFAULT_SEPARATOR: &str = "__";
+
+/// Timeout of health check pings, unless configured otherwise.
+pub const DEFAULT_HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+type ConnectFuture = Pin<Box<dyn Future<Output = Result<ProxyProvider, BoxError>> + Send>>;
+
+/// Opens a new session to a remote server on behalf of upstream sessions.
+pub(crate) type Connector = Arc<dyn Fn(Arc<Upstream>) -> ConnectFuture + Send + Sync>;
+
+/// Build a [`Connector`] from a function creating client transports.
+pub(crate) fn connector<F, Fut, T, Err, E, A>(transport: F) -> Connector
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<T, Err>> + Send + 'static,
+    Err: Into<BoxError>,
+    T: IntoTransport<RoleClient, E, A> + Send + 'static,
+    E: Error + Send + Sync + 'static,
+    A: 'static,
+{
+    let transport = Arc::new(transport);
+    Arc::new(move |upstream| {
+        let transport = transport.clone();
+        Box::pin(async move {
+            let transport = transport().await.map_err(Into::into)?;
+            Ok(ProxyProvider::connect_with(transport, upstream).await?)
+        })
+    })
+}
+
+// =============================================================================
+// Connections
+// =============================================================================
+
+/// State of the connection to a downstream server.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConnectionState {
+    /// Not connected; the connection is opened on next use.
+    Disconnected,
+    /// Connected and ready to forward requests.
+    Connected,
+    /// The last connection attempt failed.
+    Failed {
+        /// Why the attempt failed.
+        error: String,
+        /// Time left before the next attempt.
+        retry_after: Duration,
+    },
+}
+
+/// Time a session must stay open for earlier failures to be forgotten.
+const STABLE_AFTER: Duration = Duration::from_secs(30);
+
+enum Phase {
+    Disconnected,
+    Connected {
+        proxy: ProxyProvider,
+        since: Instant,
+        failures: u32,
+    },
+    Failed {
+        error: String,
+        failures: u32,
+        retry_at: Instant,
+    },
+}
+
+/// Lazily opened connection to a remote server, reopened with backoff once it fails.
+///
+/// Failed connection attempts and sessions closing within [`STABLE_AFTER`] both count
+/// as consecutive failures and delay the next attempt.
+pub(crate) struct Link {
+    name: String,
+    connect: Connector,
+    upstream: Arc<Upstream>,
+    phase: Mutex<Phase>,
+    connecting: tokio::sync::Mutex<()>,
+}
+
+impl Link {
+    pub(crate) fn new(name: String, connect: Connector) -> Self {
+        Self {
+            name,
+            connect,
+            upstream: Arc::default(),
+            phase: Mutex::new(Phase::Disconnected),
+            connecting: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Get the open session, connecting first when needed and allowed by `backoff`.
+    pub(crate) async fn provider(&self, backoff: &Backoff) -> Result<ProxyProvider, ErrorData> {
+        if let Some(proxy) = self.ready(backoff)? {
+            return Ok(proxy);
+        }
+        // Concurrent requests wait for a single connection attempt.
+        let _connecting = self.connecting.lock().await;
+        if let Some(proxy) = self.ready(backoff)? {
+            return Ok(proxy);
+        }
+        let failures = match &*self.phase.lock().unwrap() {
+            Phase::Failed { failures, .. } => *failures,
+            _ => 0,
+        };
+        match (self.connect)(self.upstream.clone()).await {
+            Ok(proxy) => {
+                *self.phase.lock().unwrap() = Phase::Connected {
+                    proxy: proxy.clone(),
+                    since: Instant::now(),
+                    failures,
+                };
+                Ok(proxy)
+            }
+            Err(error) => {
+                let error = error.to_string();
+                let failures = failures.saturating_add(1);
+                let retry_after = retry_delay(backoff, failures);
+                let unavailable = self.unavailable(&error, retry_after);
+                *self.phase.lock().unwrap() = Phase::Failed {
+                    error,
+                    failures,
+                    retry_at: Instant::now() + retry_after,
+                };
+                Err(unavailable)
+            }
+        }
+    }
+
+    /// Get the open session, or an error while waiting before the next attempt.
+    fn ready(&self, backoff: &Backoff) -> Result<Option<ProxyProvider>, ErrorData> {
+        let mut phase = self.phase.lock().unwrap();
+        if let Phase::Connected {
+            proxy,
+            since,
+            failures,
+        } = &*phase
+            && proxy.is_closed()
+        {
+            let failures = if since.elapsed() >= STABLE_AFTER {
+                0
+            } else {
+                failures.saturating_add(1)
+            };
+            *phase = Phase::Failed {
+                error: "session closed".into(),
+                failures,
+                retry_at: Instant::now() + retry_delay(backoff, failures),
+            };
+        }
+        match &*phase {
+            Phase::Connected { proxy, .. } => Ok(Some(proxy.clone())),
+            Phase::Failed {
+                error, retry_at, ..
+            } => match retry_at.checked_duration_since(Instant::now()) {
+                Some(retry_after) if !retry_after.is_zero() => {
+                    Err(self.unavailable(error, retry_after))
+                }
+                _ => Ok(None),
+            },
+            Phase::Disconnected => Ok(None),
+        }
+    }
+
+    /// Reopen a session that closed or failed, once allowed by `backoff`.
+    ///
+    /// Links that were never used stay disconnected.
+    pub(crate) async fn recover(&self, backoff: &Backoff) {
+        if matches!(*self.phase.lock().unwrap(), Phase::Disconnected) {
+            return;
+        }
+        if let Ok(None) = self.ready(backoff) {
+            // The outcome is recorded in the connection state.
+            let _ = self.provider(backoff).await;
+        }
+    }
+
+    /// Get the open session without connecting.
+    pub(crate) fn connected(&self) -> Option<ProxyProvider> {
+        match &*self.phase.lock().unwrap() {
+            Phase::Connected { proxy, .. } if !proxy.is_closed() => Some(proxy.clone()),
+            _ => None,
+        }
+    }
+
+    /// Close the session, so that the next use opens a new one.
+    pub(crate) fn disconnect(&self, error: String) {
+        *self.phase.lock().unwrap() = Phase::Failed {
+            error,
+            failures: 0,
+            retry_at: Instant::now(),
+        };
+    }
+
+    pub(crate) fn state(&self) -> ConnectionState {
+        match &*self.phase.lock().unwrap() {
+            Phase::Connected { proxy, .. } if !proxy.is_closed() => ConnectionState::Connected,
+            Phase::Failed {
+                error, retry_at, ..
+            } => ConnectionState::Failed {
+                error: error.clone(),
+                retry_after: retry_at.saturating_duration_since(Instant::now()),
+            },
+            _ => ConnectionState::Disconnected,
+        }
+    }
+
+    fn unavailable(&self, error: &str, retry_after: Duration) -> ErrorData {
+        ErrorData::internal_error(
+            format!("server '{}' is unavailable: {error}", self.name),
+            Some(json!({ "retryAfterMs": retry_after.as_millis() as u64 })),
+        )
+    }
+}
+
+/// Delay before the next connection attempt after consecutive failures.
+fn retry_delay(backoff: &Backoff, failures: u32) -> Duration {
+    match failures {
+        0 => Duration::ZERO,
+        failures => backoff.delay(failures - 1),
+    }
+}
+
+impl fmt::Debug for Link {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Link")
+            .field("name", &self.name)
+            .field("state", &self.state())
+            .finish_non_exhaustive()
+    }
+}
+
+// =============================================================================
+// Gateway
+// =============================================================================
+
+#[derive(Debug)]
+struct Mount {
+    namespace: String,
+    link: Link,
+}
+
+/// Health of a downstream server mounted in a [`Gateway`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct DownstreamStatus {
+    /// Namespace the server is mounted under.
+    pub namespace: String,
+    /// State of the connection to the server.
+    pub state: ConnectionState,
+}
+
+/// Provider aggregating several remote MCP servers.
+///
+/// - Tools and prompts of a server mounted under `git` are listed as `git__{name}` and
+///   routed back to it with their original name.
+/// - Resources keep their URIs. Reads go to each server in turn until one does not
+///   answer with "resource not found" or "method not found". Subscriptions go to the
+///   server that reads the resource, and unsubscriptions to the servers the session
+///   subscribed through.
+/// - Servers that are unavailable or lack the capability are left out of lists; other
+///   errors, such as a rejected cursor, fail the list. Requests routed to unavailable
+///   servers fail with an internal error carrying `retryAfterMs` while waiting to
+///   reconnect.
+/// - Namespaces must not overlap: mounting `git` and `git__lfs` panics, as
+///   `git__lfs__push` would be ambiguous.
+///
+/// # Example
+///
+/// ```no_run
+/// # #[cfg(feature = "child-process")]
+/// # fn compose(info: rmcp::model::Implementation) {
+/// use std::time::Duration;
+/// use rmcp::transport::TokioChildProcess;
+/// use rmcp_server_builder::{Gateway, ServerBuilder};
+/// use tokio::process::Command;
+///
+/// let gateway = Gateway::new()
+///     .mount("git", || async { TokioChildProcess::new(Command::new("mcp-git")) })
+///     .mount("docs", || async {
+///         let mut command = Command::new("mcp-docs");
+///         command.arg("--root").arg("docs");
+///         TokioChildProcess::new(command)
+///     });
+/// gateway.spawn_health_checks(Duration::from_secs(30));
+///
+/// let server = ServerBuilder::new()
+///     .info(info)
+///     .tools(gateway.clone())
+///     .prompts(gateway.clone())
+///     .resources(gateway)
+///     .build();
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Gateway {
+    mounts: Vec<Arc<Mount>>,
+    separator: String,
+    backoff: Backoff,
+    health_check_timeout: Duration,
+}
+
+impl Default for Gateway {
+    fn default() -> Self {
+        Self {
+            mounts: Vec::new(),
+            separator: DEFAULT_SEPARATOR.to_owned(),
+            backoff: Backoff::default(),
+            health_check_timeout: DEFAULT_HEALTH_CHECK_TIMEOUT,
+        }
+    }
+}
+
+impl Gateway {
+    /// Create a gateway without downstream servers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mount a remote server under a namespace.
+    ///
+    /// `transport` creates a client transport whenever a session to the server is
+    /// opened: on first use, and again after the session failed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the qualified names of the namespace could be confused with those of
+    /// an already mounted namespace, such as `git` and `git__lfs`.
+    pub fn mount<F, Fut, T, Err, E, A>(mut self, namespace: impl Into<String>, transport: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<T, Err>> + Send + 'static,
+        Err: Into<BoxError>,
+        T: IntoTransport<RoleClient, E, A> + Send + 'static,
+        E: Error + Send + Sync + 'static,
+        A: 'static,
+    {
+        let namespace = namespace.into();
+        let link = Link::new(namespace.clone(), connector(transport));
+        self.mounts.push(Arc::new(Mount { namespace, link }));
+        self.check_namespaces();
+        self
+    }
+
+    /// Set the separator between namespaces and names.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the qualified names of two mounted namespaces could be confused with
+    /// the new separator.
+    pub fn separator(mut self, separator: impl Into<String>) -> Self {
+        self.separator = separator.into();
+        self.check_namespaces();
+        self
+    }
+
+    /// Set the delays between attempts to reconnect to a failing server.
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Set how long health checks wait for a server to answer a ping.
+    pub fn health_check_timeout(mut self, timeout: Duration) -> Self {
+        self.health_check_timeout = timeout;
+        self
+    }
+
+    /// Get the connection state of every mounted server.
+    pub fn status(&self) -> Vec<DownstreamStatus> {
+        self.mounts
+            .iter()
+            .map(|mount| DownstreamStatus {
+                namespace: mount.namespace.clone(),
+                state: mount.link.state(),
+            })
+            .collect()
+    }
+
+    /// Check the health of every mounted server.
+    ///
+    /// Connected servers are pinged, and reconnected when they do not answer in time.
+    /// Servers whose session closed or whose last connection attempt failed are
+    /// reconnected once their backoff delay has elapsed. Servers that were never used
+    /// stay disconnected.
+    pub async fn check_health(&self) -> Vec<DownstreamStatus> {
+        for mount in &self.mounts {
+            check(mount, &self.backoff, self.health_check_timeout).await;
+        }
+        self.status()
+    }
+
+    /// Check the health of every mounted server periodically in a background task.
+    ///
+    /// The task stops once the gateway and all its clones are dropped.
+    pub fn spawn_health_checks(&self, interval: Duration) -> JoinHandle<()> {
+        let mounts: Vec<Weak<Mount>> = self.mounts.iter().map(Arc::downgrade).collect();
+        let backoff = self.backoff;
+        let timeout = self.health_check_timeout;
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticks.tick().await;
+                let mounts: Vec<_> = mounts.iter().filter_map(Weak::upgrade).collect();
+                if mounts.is_empty() {
+                    return;
+                }
+                for mount in mounts {
+                    check(&mount, &backoff, timeout).await;
+                }
+            }
+        })
+    }
+
+    /// Check that every qualified name resolves to a single mount.
+    fn check_namespaces(&self) {
+        let prefixes: Vec<_> = self
+            .mounts
+            .iter()
+            .map(|mount| format!("{}{}", mount.namespace, self.separator))
+            .collect();
+        for (index, prefix) in prefixes.iter().enumerate() {
+            for other in &prefixes[..index] {
+                assert!(
+                    !prefix.starts_with(other.as_str()) && !other.starts_with(prefix.as_str()),
+                    "namespace '{}' overlaps with an already mounted namespace",
+                    self.mounts[index].namespace
+                );
+            }
+        }
+    }
+
+    fn qualify(&self, mount: &Mount, name: &str) -> String {
+        format!("{}{}{name}", mount.namespace, self.separator)
+    }
+
+    /// Find the server mounted under the namespace of `name`, and the name within it.
+    fn resolve<'a>(&self, name: &'a str) -> Option<(&Mount, &'a str)> {
+        self.mounts.iter().find_map(|mount| {
+            let name = name
+                .strip_prefix(mount.namespace.as_str())?
+                .strip_prefix(self.separator.as_str())?;
+            Some((mount.as_ref(), name))
+        })
+    }
+
+    /// Serve a page of a list spanning all available servers.
+    async fn page<'s, T, F, Fut>(
+        &'s self,
+        request: Option<PaginatedRequestParams>,
+        mut fetch: F,
+    ) -> Result<(Vec<T>, Option<Cursor>), ErrorData>
+    where
+        F: FnMut(&'s Mount, ProxyProvider, Option<PaginatedRequestParams>) -> Fut,
+        Fut: Future<Output = Result<(Vec<T>, Option<Cursor>), ErrorData>>,
+    {
+        let lists = self.mounts.len();
+        let mut position =
+            Position::decode(request.as_ref().and_then(|r| r.cursor.as_deref()), lists)?;
+        while let Some(mount) = self.mounts.get(position.index) {
+            let params = position.request(&request);
+            // Unavailable servers, and servers without the capability, are left out.
+            let listed = match mount.link.provider(&self.backoff).await {
+                Ok(proxy) => match fetch(mount, proxy.clone(), params).await {
+                    Err(error) if error.code == ErrorCode::METHOD_NOT_FOUND => None,
+                    Err(_) if proxy.is_closed() => None,
+                    result => Some(result?),
+                },
+                Err(_) => None,
+            };
+            if let Some((items, next)) = listed {
+                return Ok((items, position.next(next, lists)));
+            }
+            position = Position {
+                index: position.index + 1,
+                cursor: None,
+            };
+        }
+        Ok((Vec::new(), None))
+    }
+
+    /// Send a resource request to each available server until one handles it.
+    async fn route_resource<T, F, Fut>(&self, uri: &str, mut send: F) -> Result<T, ErrorData>
+    where
+        F: FnMut(ProxyProvider) -> Fut,
+        Fut: Future<Output = Result<T, ErrorData>>,
+    {
+        for mount in &self.mounts {
+            let Ok(proxy) = mount.link.provider(&self.backoff).await else {
+                continue;
+            };
+            match send(proxy).await {
+                Err(error) if falls_through(&error) => continue,
+                result => return result,
+            }
+        }
+        Err(ErrorData::resource_not_found(
+            format!("resource '{uri}' is not available"),
+            None,
+        ))
+    }
+
+    /// Get the server a resource belongs to: the first available one that reads it.
+    async fn owner(
+        &self,
+        uri: &str,
+        context: &RequestContext<RoleServer>,
+    ) -> Result<ProxyProvider, ErrorData> {
+        self.route_resource(uri, |proxy| {
+            let request = ReadResourceRequestParams {
+                meta: None,
+                uri: uri.to_owned(),
+            };
+            let context = context.clone();
+            async move {
+                proxy.read_resource(request, context).await?;
+                Ok(proxy)
+            }
+        })
+        .await
+    }
+
+    async fn provider(&self, mount: &Mount) -> Result<ProxyProvider, ErrorData> {
+        mount.link.provider(&self.backoff).await
+    }
+}
+
+async fn check(mount: &Mount, backoff: &Backoff, timeout: Duration) {
+    if let Some(proxy) = mount.link.connected()
+        && let Err(error) = proxy.ping(timeout).await
+    {
+        mount
+            .link
+            .disconnect(format!("health check failed: {error}"));
+    }
+    mount.link.recover(backoff).await;
+}
+
+// =============================================================================
+// Provider implementations
+// =============================================================================
+
+impl ToolsProvider for Gateway {
+    async fn list_tools(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        let (tools, next_cursor) = self
+            .page(request, |mount, proxy, params| {
+                let context = context.clone();
+                async move {
+                    let result = proxy.list_tools(params, context).await?;
+                    let tools = result
+                        .tools
+                        .into_iter()
+                        .map(|mut tool| {
+                            tool.name = self.qualify(mount, &tool.name).into();
+                            tool
+                        })
+                        .collect();
+                    Ok((tools, result.next_cursor))
+                }
+            })
+            .await?;
+        Ok(ListToolsResult {
+            meta: None,
+            next_cursor,
+            tools,
+        })
+    }
+
+    async fn call_tool(
+        &self,
+        mut request: CallToolRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let Some((mount, name)) = self.resolve(&request.name) else {
+            return Err(ErrorData::invalid_params(
+                format!("tool '{}' is not available", request.name),
+                None,
+            ));
+        };
+        request.name = name.to_owned().into();
+        self.provider(mount)
+            .await?
+            .call_tool(request, context)
+            .await
+    }
+}
+
+impl PromptsProvider for Gateway {
+    async fn list_prompts(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, ErrorData> {
+        let (prompts, next_cursor) = self
+            .page(request, |mount, proxy, params| {
+                let context = context.clone();
+                async move {
+                    let result = proxy.list_prompts(params, context).await?;
+                    let prompts = result
+                        .prompts
+                        .into_iter()
+                        .map(|mut prompt| {
+                            prompt.name = self.qualify(mount, &prompt.name);
+                            prompt
+                        })
+                        .collect();
+                    Ok((prompts, result.next_cursor))
+                }
+            })
+            .await?;
+        Ok(ListPromptsResult {
+            meta: None,
+            next_cursor,
+            prompts,
+        })
+    }
+
+    async fn get_prompt(
+        &self,
+        mut request: GetPromptRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, ErrorData> {
+        let Some((mount, name)) = self.resolve(&request.name) else {
+            return Err(ErrorData::invalid_params(
+                format!("prompt '{}' is not available", request.name),
+                None,
+            ));
+        };
+        request.name = name.to_owned();
+        self.provider(mount)
+            .await?
+            .get_prompt(request, context)
+            .await
+    }
+}
+
+impl ResourcesProvider for Gateway {
+    async fn list_resources(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, ErrorData> {
+        let (resources, next_cursor) = self
+            .page(request, |_, proxy, params| {
+                let context = context.clone();
+                async move {
+                    let result = proxy.list_resources(params, context).await?;
+                    Ok((result.resources, result.next_cursor))
+                }
+            })
+            .await?;
+        Ok(ListResourcesResult {
+            meta: None,
+            next_cursor,
+            resources,
+        })
+    }
+
+    async fn list_resource_templates(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, ErrorData> {
+        let (resource_templates, next_cursor) = self
+            .page(request, |_, proxy, params| {
+                let context = context.clone();
+                async move {
+                    let result = proxy.list_resource_templates(params, context).await?;
+                    Ok((result.resource_templates, result.next_cursor))
+                }
+            })
+            .await?;
+        Ok(ListResourceTemplatesResult {
+            meta: None,
+            next_cursor,
+            resource_templates,
+        })
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, ErrorData> {
+        self.route_resource(&request.uri, |proxy| {
+            let (request, context) = (request.clone(), context.clone());
+            async move { proxy.read_resource(request, context).await }
+        })
+        .await
+    }
+
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        let owner = self.owner(&request.uri, &context).await?;
+        owner.subscribe(request, context).await
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        let mut result = Ok(());
+        for mount in &self.mounts {
+            if !mount.link.upstream.is_subscribed(&context, &request.uri) {
+                continue;
+            }
+            let unsubscribed = match self.provider(mount).await {
+                Ok(proxy) => proxy.unsubscribe(request.clone(), context.clone()).await,
+                Err(error) => Err(error),
+            };
+            result = result.and(unsubscribed);
+        }
+        result
+    }
+}
+
+impl CompletionProvider for Gateway {
+    async fn complete(
+        &self,
+        mut request: CompleteRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CompleteResult, ErrorData> {
+        match &mut request.r#ref {
+            Reference::Prompt(prompt) => {
+                let Some((mount, name)) = self.resolve(&prompt.name) else {
+                    return Err(ErrorData::invalid_params(
+                        format!("prompt '{}' is not available", prompt.name),
+                        None,
+                    ));
+                };
+                prompt.name = name.to_owned();
+                self.provider(mount).await?.complete(request, context).await
+            }
+            Reference::Resource(resource) => {
+                let uri = resource.uri.clone();
+                self.route_resource(&uri, |proxy| {
+                    let (request, context) = (request.clone(), context.clone());
+                    async move { proxy.complete(request, context).await }
+                })
+                .await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::io;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use rmcp::handler::client::ClientHandler;
+    use rmcp::model::{
+        Content, Implementation, ResourceContents, ResourceUpdatedNotificationParam, Tool,
+    };
+    use rmcp::service::NotificationContext;
+    use rmcp::{Peer, ServiceExt};
+    use tokio::io::DuplexStream;
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::test_support::{self, call};
+    use crate::{Paginated, ServerBuilder, ServerHandler};
+
+    struct Named(&'static str);
+
+    impl ToolsProvider for Named {
+        async fn list_tools(
+            &self,
+            _request: Option<PaginatedRequestParams>,
+            _context: RequestContext<RoleServer>,
+        ) -> Result<ListToolsResult, ErrorData> {
+            let tool = Tool::new("whoami", "Name the server", Arc::new(Default::default()));
+            Ok(ListToolsResult::with_all_items(vec![tool]))
+        }
+
+        async fn call_tool(
+            &self,
+            _request: CallToolRequestParams,
+            _context: RequestContext<RoleServer>,
+        ) -> Result<CallToolResult, ErrorData> {
+            Ok(CallToolResult::success(vec![Content::text(self.0)]))
+        }
+    }
+
+    /// Resources provider serving a single document, accepting any subscription.
+    #[derive(Clone)]
+    struct Document {
+        uri: &'static str,
+        subscriber: Arc<Mutex<Option<Peer<RoleServer>>>>,
+    }
+
+    impl Document {
+        fn new(uri: &'static str) -> Self {
+            Self {
+                uri,
+                subscriber: Arc::default(),
+            }
+        }
+
+        /// Announce an update of the document to the last session that subscribed.
+        async fn update(&self) {
+            let peer = self.subscriber.lock().unwrap().clone().unwrap();
+            peer.notify_resource_updated(ResourceUpdatedNotificationParam {
+                uri: self.uri.into(),
+            })
+            .await
+            .unwrap();
+        }
+
+        fn mount(
+            &self,
+        ) -> impl Fn() -> std::future::Ready<Result<DuplexStream, Infallible>> + use<> {
+            let document = self.clone();
+            move || {
+                let server = ServerBuilder::new()
+                    .info(info(document.uri))
+                    .resources(document.clone())
+                    .build();
+                std::future::ready(serve(server))
+            }
+        }
+    }
+
+    impl ResourcesProvider for Document {
+        async fn list_resources(
+            &self,
+            _request: Option<PaginatedRequestParams>,
+            _context: RequestContext<RoleServer>,
+        ) -> Result<ListResourcesResult, ErrorData> {
+            Ok(ListResourcesResult::default())
+        }
+
+        async fn list_resource_templates(
+            &self,
+            _request: Option<PaginatedRequestParams>,
+            _context: RequestContext<RoleServer>,
+        ) -> Result<ListResourceTemplatesResult, ErrorData> {
+            Ok(ListResourceTemplatesResult::default())
+        }
+
+        async fn read_resource(
+            &self,
+            request: ReadResourceRequestParams,
+            _context: RequestContext<RoleServer>,
+        ) -> Result<ReadResourceResult, ErrorData> {
+            match request.uri == self.uri {
+                true => Ok(ReadResourceResult {
+                    contents: vec![ResourceContents::text("", request.uri)],
+                }),
+                false => Err(ErrorData::resource_not_found("no such document", None)),
+            }
+        }
+
+        async fn subscribe(
+            &self,
+            _request: SubscribeRequestParams,
+            context: RequestContext<RoleServer>,
+        ) -> Result<(), ErrorData> {
+            *self.subscriber.lock().unwrap() = Some(context.peer);
+            Ok(())
+        }
+
+        async fn unsubscribe(
+            &self,
+            _request: UnsubscribeRequestParams,
+            _context: RequestContext<RoleServer>,
+        ) -> Result<(), ErrorData> {
+            Ok(())
+        }
+    }
+
+    /// Client reporting the resource updates it receives.
+    struct Updates(mpsc::UnboundedSender<String>);
+
+    impl ClientHandler for Updates {
+        async fn on_resource_updated(
+            &self,
+            params: ResourceUpdatedNotificationParam,
+            _context: NotificationContext<RoleClient>,
+        ) {
+            let _ = self.0.send(params.uri);
+        }
+    }
+
+    fn spawn_server(name: &'static str) -> Result<DuplexStream, Infallible> {
+        let server = ServerBuilder::new()
+            .info(info(name))
+            .tools(Paginated::new(Named(name)))
+            .build();
+        serve(server)
+    }
+
+    fn info(name: &'static str) -> Implementation {
+        Implementation {
+            name: name.into(),
+            version: "1.0.0".into(),
+            ..Default::default()
+        }
+    }
+
+    fn serve(server: impl ServerHandler) -> Result<DuplexStream, Infallible> {
+        Ok(test_support::serve(server))
+    }
+
+    #[test]
+    #[should_panic(expected = "namespace 'git__lfs' overlaps")]
+    fn test_rejects_overlapping_namespaces() {
+        let _ = Gateway::new()
+            .mount("git", || async { spawn_server("git") })
+            .mount("git__lfs", || async { spawn_server("lfs") });
+    }
+
+    #[test]
+    #[should_panic(expected = "namespace 'git' overlaps")]
+    fn test_rejects_duplicate_namespaces() {
+        let _ = Gateway::new()
+            .mount("git", || async { spawn_server("git") })
+            .mount("git", || async { spawn_server("git") });
+    }
+
+    #[test]
+    #[should_panic(expected = "overlaps")]
+    fn test_rejects_separator_making_namespaces_overlap() {
+        let _ = Gateway::new()
+            .mount("git", || async { spawn_server("git") })
+            .mount("git_lfs", || async { spawn_server("lfs") })
+            .separator("_");
+    }
+
+    #[tokio::test]
+    async fn test_gateway() {
+        let gateway = Gateway::new()
+            .mount("a", || async { spawn_server("a") })
+            .mount("down", || async {
+                Err::<DuplexStream, _>(io::Error::other("refused"))
+            })
+            .mount("b", || async { spawn_server("b") });
+        let server = ServerBuilder::new()
+            .info(Implementation::from_build_env())
+            .tools(gateway.clone())
+            .build();
+        let client = test_support::connect(server, ()).await;
+
+        let tools = client.list_all_tools().await.unwrap();
+        let names: Vec<_> = tools.iter().map(|tool| tool.name.as_ref()).collect();
+        assert_eq!(names, ["a__whoami", "b__whoami"]);
+
+        let result = client.call_tool(call("b__whoami")).await.unwrap();
+        assert_eq!(result.content[0].as_text().unwrap().text, "b");
+        assert!(client.call_tool(call("c__whoami")).await.is_err());
+        assert!(client.call_tool(call("down__whoami")).await.is_err());
+
+        let status = gateway.status();
+        assert_eq!(status[0].state, ConnectionState::Connected);
+        assert!(
+            matches!(status[1].state, ConnectionState::Failed { ref error, .. } if error == "refused")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_link_restarts_exited_server() {
+        let starts = Arc::new(AtomicUsize::new(0));
+        let exit = Arc::new(tokio::sync::Notify::new());
+        let (counter, signal) = (starts.clone(), exit.clone());
+        let link = Link::new(
+            "flaky".into(),
+            connector(move || {
+                counter.fetch_add(1, Ordering::Relaxed);
+                let exit = signal.clone();
+                async move {
+                    let (server_io, client_io) = tokio::io::duplex(4096);
+                    let server = ServerBuilder::new().info(info("flaky")).build();
+                    tokio::spawn(async move {
+                        let service = server.serve(server_io).await.unwrap();
+                        exit.notified().await;
+                        service.cancel().await
+                    });
+                    Ok::<_, Infallible>(client_io)
+                }
+            }),
+        );
+        let backoff = Backoff::fixed(Duration::from_millis(50));
+
+        let proxy = link.provider(&backoff).await.unwrap();
+        assert_eq!(link.state(), ConnectionState::Connected);
+
+        // The server exits after a successful start.
+        exit.notify_one();
+        for _ in 0..100 {
+            if proxy.is_closed() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(proxy.is_closed());
+
+        // Closing right after starting counts as a failure and delays the restart.
+        link.recover(&backoff).await;
+        assert!(matches!(
+            link.state(),
+            ConnectionState::Failed { ref error, .. } if error == "session closed"
+        ));
+        assert_eq!(starts.load(Ordering::Relaxed), 1);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        link.recover(&backoff).await;
+        assert_eq!(link.state(), ConnectionState::Connected);
+        assert_eq!(starts.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn test_list_errors() {
+        let gateway = Gateway::new()
+            .mount("empty", || async {
+                serve(ServerBuilder::new().info(info("empty")).build())
+            })
+            .mount("a", || async { spawn_server("a") });
+        let server = ServerBuilder::new()
+            .info(Implementation::from_build_env())
+            .tools(gateway)
+            .build();
+        let client = test_support::connect(server, ()).await;
+        let list = |cursor: &str| {
+            client.list_tools(Some(PaginatedRequestParams {
+                meta: None,
+                cursor: Some(cursor.into()),
+            }))
+        };
+
+        // A server without tools is left out.
+        let tools = client.list_all_tools().await.unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, "a__whoami");
+
+        // Cursors the servers reject are reported rather than skipped.
+        for cursor in ["1:bogus", "7"] {
+            let error = list(cursor).await.unwrap_err();
+            assert!(matches!(
+                error,
+                rmcp::service::ServiceError::McpError(ErrorData { code, .. })
+                    if code == ErrorCode::INVALID_PARAMS
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subscriptions_follow_the_resource_owner() {
+        let (first, second) = (Document::new("doc://a"), Document::new("doc://b"));
+        let gateway = Gateway::new()
+            .mount("a", first.mount())
+            .mount("b", second.mount());
+        let server = ServerBuilder::new()
+            .info(Implementation::from_build_env())
+            .resources(gateway)
+            .build();
+        let (updates, mut received) = mpsc::unbounded_channel();
+        let client = test_support::connect(server, Updates(updates)).await;
+
+        client
+            .subscribe(SubscribeRequestParams {
+                meta: None,
+                uri: "doc://b".into(),
+            })
+            .await
+            .unwrap();
+        assert!(first.subscriber.lock().unwrap().is_none());
+        second.update().await;
+        let uri = tokio::time::timeout(Duration::from_secs(1), received.recv()).await;
+        assert_eq!(uri.unwrap().as_deref(), Some("doc://b"));
+
+        client
+            .unsubscribe(UnsubscribeRequestParams {
+                meta: None,
+                uri: "doc://b".into(),
+            })
+            .await
+            .unwrap();
+        second.update().await;
+        let uri = tokio::time::timeout(Duration::from_millis(100), received.recv()).await;
+        assert!(uri.is_err());
+    }
+}
@@ -9,10 +9,11 @@
 //! Instead of implementing the full `ServerHandler` trait and delegating methods,
 //! you can compose a server from individual providers:
 //!
-//! ```ignore
-//! use rmcp_server_builder::{Server, ServerBuilder};
+//! ```no_run
+//! use rmcp_server_builder::{PromptsProvider, Server, ServerBuilder, ToolsProvider};
 //! use rmcp::model::Implementation;
 //!
+//! # fn compose(openapi_server: impl ToolsProvider, my_prompts: impl PromptsProvider) {
 //! // Compose a server with tools from one provider and prompts from another
 //! let server = ServerBuilder::new()
 //!     .info(Implementation::from_build_env())
@@ -20,6 +21,7 @@
 //!     .tools(openapi_server)
 //!     .prompts(my_prompts)
 //!     .build();
+//! # }
 //! ```
 //!
 //! # Provider Traits
@@ -42,17 +44,105 @@
 //!
 //! The composed server automatically sets capability flags based on which providers
 //! are configured. If you set a tools provider, `capabilities.tools` will be enabled.
+//!
+//! # Adapters
+//!
+//! Providers can be wrapped before they reach the server, either directly or through
+//! the matching [`ServerBuilder`] method. Each adapter documents its behaviour:
+//!
+//! - [`Paginated`] and [`Merged`] page through and combine provider lists
+//! - [`FilteredTools`], [`RewrittenTools`] and [`AliasedTools`] shape the exposed tools
+//! - [`RateLimited`], [`Retry`], [`Fallback`] and [`GuardedTools`] protect tool calls
+//! - [`Audited`] reports tool calls to an [`AuditSink`]
+//! - [`CachedTools`], [`CachedResources`] and [`CachedLists`] memoize results
+//!
+//! Requests can be denied by an [`Authorizer`] such as a [`Policy`]. Providers reach
+//! per-session data through [`SessionState`] and can call back to the client with
+//! [`Sampling`] and [`Elicitation`]. [`ServerFactory`] hosts one server per session.
+//!
+//! # Features
+//!
+//! - `proxy`: `ProxyProvider` and `Gateway` forward requests to remote servers
+//! - `child-process`: `ChildProcessProvider` runs a remote server as a subprocess
+//! - `stdio`, `http`: `Server::serve_stdio` and `Server::serve_http`
+//! - `tracing`, `opentelemetry`: an `mcp.request` span around every request, and
+//!   warnings for tool aliases and property descriptions that cannot be applied
+//! - `metrics`: `mcp_requests_total`, `mcp_request_errors_total` and
+//!   `mcp_request_duration_seconds`, labelled by `method` and `tool`; calls to tools
+//!   not listed to the session are labelled `unknown`
+//! - `regex`: regular expression matching in [`ToolFilter`]
 
+mod alias;
+mod audit;
+mod auth;
 mod builder;
+mod cache;
+#[cfg(feature = "child-process")]
+mod child_process;
+mod circuit_breaker;
+mod composite;
+mod elicitation;
+mod factory;
+mod filter;
+#[cfg(feature = "proxy")]
+mod gateway;
+mod pagination;
 mod providers;
+#[cfg(feature = "proxy")]
+mod proxy;
+mod rate_limit;
+mod request;
+mod retry;
+mod rewrite;
+mod sampling;
+#[cfg(any(feature = "stdio", feature = "http"))]
+mod serve;
 mod server;
+mod session;
+#[cfg(any(feature = "tracing", feature = "metrics"))]
+mod telemetry;
+#[cfg(test)]
+mod test_support;
 
+pub use alias::{AliasedTools, DEPRECATION_META_KEY, ToolAliases};
+pub use audit::{AuditLog, AuditRecord, AuditSink, AuditStatus, Audited, JsonLines, REDACTED};
+pub use auth::{ACCESS_DENIED, Authorizer, Identity, Match, Policy, Require, access_denied};
 pub use builder::{ServerBuilder, SimpleInfo};
+pub use cache::{
+    CacheConfig, CachedLists, CachedResources, CachedTools, DEFAULT_MAX_ENTRIES, DEFAULT_TTL,
+    ListCache, ResultCache,
+};
+#[cfg(feature = "child-process")]
+pub use child_process::ChildProcessProvider;
+pub use circuit_breaker::{
+    CIRCUIT_OPEN, CircuitBreaker, CircuitState, CircuitStatus, GuardedTools,
+};
+pub use composite::{Merged, NOT_FOUND, not_found};
+pub use elicitation::{Elicitation, ElicitationOutcome};
+pub use factory::{ServerFactory, Shared, SharedServer};
+pub use filter::{FilteredTools, ToolFilter};
+#[cfg(feature = "proxy")]
+pub use gateway::{
+    ConnectionState, DEFAULT_HEALTH_CHECK_TIMEOUT, DEFAULT_SEPARATOR, DownstreamStatus, Gateway,
+};
+pub use pagination::{DEFAULT_PAGE_SIZE, Paginated};
 pub use providers::{
     CompletionProvider, LoggingProvider, PromptsProvider, ResourcesProvider, ServerInfoProvider,
     ToolsProvider,
 };
+#[cfg(feature = "proxy")]
+pub use proxy::ProxyProvider;
+pub use rate_limit::{Quota, RATE_LIMITED, Rate, RateLimited, RateLimits};
+pub use request::{Capability, RequestKind};
+pub use retry::{Backoff, Fallback, Retry, RetryPolicy};
+pub use rewrite::{RewrittenTools, ToolOverride, ToolOverrides};
+pub use sampling::{DEFAULT_SAMPLING_TIMEOUT, Sampling, SamplingReply};
+#[cfg(feature = "http")]
+pub use serve::HTTP_PATH;
+#[cfg(any(feature = "stdio", feature = "http"))]
+pub use serve::Serving;
 pub use server::{Server, Unset};
+pub use session::{ConnectedClient, SessionId, SessionState};
 
 // Re-export commonly used rmcp types for convenience
 pub use rmcp::handler::server::ServerHandler;
@@ -142,6 +232,19 @@ mod tests {
             .build();
     }
 
+    #[test]
+    fn test_builder_filter_tools() {
+        let _server = ServerBuilder::new()
+            .info(Implementation {
+                name: "test".into(),
+                version: "1.0.0".into(),
+                ..Default::default()
+            })
+            .tools(TestToolsProvider)
+            .filter_tools(ToolFilter::new().deny_name("hidden"))
+            .build();
+    }
+
     #[test]
     fn test_server_builder_static_method() {
         let _server = Server::<Unset, Unset, Unset, Unset, Unset, Unset>::builder()
@@ -152,4 +255,18 @@ mod tests {
             })
             .build();
     }
+
+    #[test]
+    fn test_clone_gets_new_session_id() {
+        let server = ServerBuilder::new()
+            .info(Implementation {
+                name: "test".into(),
+                version: "1.0.0".into(),
+                ..Default::default()
+            })
+            .build();
+        let clone = server.clone();
+        assert_ne!(clone.session_id(), server.session_id());
+        assert_ne!(clone.clone().session_id(), clone.session_id());
+    }
 }
@@ -0,0 +1,676 @@
+//! Automatic pagination for providers that return complete lists.
+//!
+//! [`Paginated`] wraps a tools, prompts or resources provider, fetches the full list
+//! once per session and serves it in fixed-size pages. Cursors are opaque and signed
+//! with a per-adapter secret, so clients cannot forge or alter them. Snapshots are kept
+//! in the [`SessionState`] of the session, and dropped with it when the session ends.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::{BuildHasher, RandomState};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use rmcp::{
+    model::{
+        CallToolRequestParams, CallToolResult, Cursor, ErrorData, GetPromptRequestParams,
+        GetPromptResult, ListPromptsResult, ListResourceTemplatesResult, ListResourcesResult,
+        ListToolsResult, Meta, PaginatedRequestParams, Prompt, ReadResourceRequestParams,
+        ReadResourceResult, Resource, ResourceTemplate, SubscribeRequestParams, Tool,
+        UnsubscribeRequestParams,
+    },
+    service::{RequestContext, RoleServer},
+};
+
+use crate::cache::CacheHooks;
+use crate::providers::{PromptsProvider, ResourcesProvider, ToolsProvider};
+use crate::session::{SessionId, SessionState};
+
+/// Number of items per page when none is configured.
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// Maximum number of sessions whose snapshots are kept per list outside of a composed
+/// server, where there is no session state to keep them in.
+const MAX_SNAPSHOTS: usize = 1024;
+
+/// Maximum number of upstream pages fetched for a single list.
+const MAX_UPSTREAM_PAGES: usize = 1000;
+
+/// Adapter that serves a provider's lists in fixed-size pages.
+///
+/// The first request of a listing (without a cursor) fetches the complete list from the
+/// inner provider, following its own cursors if it paginates, and caches it for the
+/// current session. Subsequent requests with a cursor are served from that snapshot.
+/// A cursor becomes invalid once the session starts a new listing.
+///
+/// # Example
+///
+/// ```no_run
+/// use rmcp::model::Implementation;
+/// use rmcp_server_builder::{Paginated, ServerBuilder, ToolsProvider};
+///
+/// # fn compose(large_catalogue: impl ToolsProvider) {
+/// let server = ServerBuilder::new()
+///     .info(Implementation::from_build_env())
+///     .tools(Paginated::new(large_catalogue).page_size(50))
+///     .build();
+/// # }
+/// ```
+pub struct Paginated<P> {
+    inner: P,
+    page_size: usize,
+    signer: CursorSigner,
+    tools: PageCache<Tool>,
+    prompts: PageCache<Prompt>,
+    resources: PageCache<Resource>,
+    resource_templates: PageCache<ResourceTemplate>,
+}
+
+impl<P> Paginated<P> {
+    /// Wrap a provider with the default page size.
+    pub fn new(inner: P) -> Self {
+        Self {
+            inner,
+            page_size: DEFAULT_PAGE_SIZE,
+            signer: CursorSigner::new(),
+            tools: PageCache::new("tools"),
+            prompts: PageCache::new("prompts"),
+            resources: PageCache::new("resources"),
+            resource_templates: PageCache::new("resource_templates"),
+        }
+    }
+
+    /// Set the number of items per page.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `page_size` is zero.
+    pub fn page_size(mut self, page_size: usize) -> Self {
+        assert!(page_size > 0, "page size must be greater than zero");
+        self.page_size = page_size;
+        self
+    }
+
+    /// Get a reference to the wrapped provider.
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+}
+
+/// Fetch every page of an upstream list, following its cursors.
+///
+/// Fails with an internal error when the upstream repeats a cursor or returns more than
+/// [`MAX_UPSTREAM_PAGES`] pages, rather than looping forever.
+pub(crate) async fn fetch_all<T, F, Fut>(
+    meta: Option<Meta>,
+    mut fetch: F,
+) -> Result<Vec<T>, ErrorData>
+where
+    F: FnMut(PaginatedRequestParams) -> Fut,
+    Fut: Future<Output = Result<(Vec<T>, Option<Cursor>), ErrorData>>,
+{
+    let mut items = Vec::new();
+    let mut cursor = None;
+    let mut seen = HashSet::new();
+    for _ in 0..MAX_UPSTREAM_PAGES {
+        let (page, next_cursor) = fetch(PaginatedRequestParams {
+            meta: meta.clone(),
+            cursor,
+        })
+        .await?;
+        items.extend(page);
+        match next_cursor {
+            Some(next) if !seen.insert(next.clone()) => {
+                return Err(ErrorData::internal_error(
+                    format!("upstream list repeated cursor {next:?}"),
+                    None,
+                ));
+            }
+            Some(next) => cursor = Some(next),
+            None => return Ok(items),
+        }
+    }
+    Err(ErrorData::internal_error(
+        format!("upstream list has more than {MAX_UPSTREAM_PAGES} pages"),
+        None,
+    ))
+}
+
+// =============================================================================
+// Cursors
+// =============================================================================
+
+/// Signs and verifies cursors with a random SipHash key.
+struct CursorSigner {
+    key: RandomState,
+}
+
+impl CursorSigner {
+    fn new() -> Self {
+        Self {
+            key: RandomState::new(),
+        }
+    }
+
+    fn mac(&self, list: &str, session: Option<SessionId>, generation: u64, offset: u64) -> u64 {
+        self.key
+            .hash_one((list, session.map(|id| id.as_u64()), generation, offset))
+    }
+
+    fn encode(
+        &self,
+        list: &str,
+        session: Option<SessionId>,
+        generation: u64,
+        offset: u64,
+    ) -> Cursor {
+        let mac = self.mac(list, session, generation, offset);
+        format!("{generation:016x}{offset:016x}{mac:016x}")
+    }
+
+    /// Decode a cursor into `(generation, offset)`, rejecting altered cursors.
+    fn decode(&self, list: &str, session: Option<SessionId>, cursor: &str) -> Option<(u64, u64)> {
+        if cursor.len() != 48 || !cursor.is_ascii() {
+            return None;
+        }
+        let field = |i: usize| u64::from_str_radix(&cursor[i * 16..(i + 1) * 16], 16).ok();
+        let (generation, offset, mac) = (field(0)?, field(1)?, field(2)?);
+        (mac == self.mac(list, session, generation, offset)).then_some((generation, offset))
+    }
+}
+
+fn invalid_cursor() -> ErrorData {
+    ErrorData::invalid_params("invalid or expired cursor", None)
+}
+
+// =============================================================================
+// Snapshot cache
+// =============================================================================
+
+struct Snapshot<T> {
+    generation: u64,
+    items: Arc<Vec<T>>,
+}
+
+/// Snapshots of the lists of type `T` stored in a session's state, by cache.
+struct SessionSnapshots<T>(HashMap<u64, Snapshot<T>>);
+
+impl<T> Default for SessionSnapshots<T> {
+    fn default() -> Self {
+        Self(HashMap::new())
+    }
+}
+
+/// Where the snapshot of a session is kept.
+#[derive(Clone, Copy)]
+struct Scope<'a> {
+    session: Option<SessionId>,
+    state: Option<&'a SessionState>,
+}
+
+impl<'a> Scope<'a> {
+    fn new(context: &'a RequestContext<RoleServer>) -> Self {
+        Self {
+            session: SessionId::from_context(context),
+            state: context.extensions.get::<SessionState>(),
+        }
+    }
+}
+
+/// Per-session snapshots of a single list.
+struct PageCache<T> {
+    list: &'static str,
+    id: u64,
+    next_generation: AtomicU64,
+    snapshots: Mutex<HashMap<Option<SessionId>, Snapshot<T>>>,
+}
+
+impl<T: Clone + Send + Sync + 'static> PageCache<T> {
+    fn new(list: &'static str) -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        Self {
+            list,
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            next_generation: AtomicU64::new(0),
+            snapshots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn store(&self, scope: Scope<'_>, items: Vec<T>) -> (u64, Arc<Vec<T>>) {
+        let generation = self.next_generation.fetch_add(1, Ordering::Relaxed);
+        let items = Arc::new(items);
+        let snapshot = Snapshot {
+            generation,
+            items: items.clone(),
+        };
+        if let Some(state) = scope.state {
+            state.update_or_default(|snapshots: &mut SessionSnapshots<T>| {
+                snapshots.0.insert(self.id, snapshot);
+            });
+            return (generation, items);
+        }
+
+        let session = scope.session;
+        let mut snapshots = self.snapshots.lock().unwrap();
+        if snapshots.len() >= MAX_SNAPSHOTS && !snapshots.contains_key(&session) {
+            let oldest = snapshots
+                .iter()
+                .min_by_key(|(_, snapshot)| snapshot.generation)
+                .map(|(session, _)| *session);
+            if let Some(oldest) = oldest {
+                snapshots.remove(&oldest);
+            }
+        }
+        snapshots.insert(session, snapshot);
+        (generation, items)
+    }
+
+    fn load(&self, scope: Scope<'_>, generation: u64) -> Option<Arc<Vec<T>>> {
+        let matching = |snapshot: &Snapshot<T>| {
+            (snapshot.generation == generation).then(|| snapshot.items.clone())
+        };
+        match scope.state {
+            Some(state) => state
+                .update(|snapshots: &mut SessionSnapshots<T>| {
+                    snapshots.0.get(&self.id).and_then(matching)
+                })
+                .flatten(),
+            None => {
+                let snapshots = self.snapshots.lock().unwrap();
+                snapshots.get(&scope.session).and_then(matching)
+            }
+        }
+    }
+
+    /// Serve one page, fetching a fresh snapshot when no cursor is given.
+    async fn page<Fut>(
+        &self,
+        signer: &CursorSigner,
+        page_size: usize,
+        scope: Scope<'_>,
+        cursor: Option<&str>,
+        fetch: impl FnOnce() -> Fut,
+    ) -> Result<(Vec<T>, Option<Cursor>), ErrorData>
+    where
+        Fut: Future<Output = Result<Vec<T>, ErrorData>>,
+    {
+        let (generation, offset, items) = match cursor {
+            None => {
+                let (generation, items) = self.store(scope, fetch().await?);
+                (generation, 0, items)
+            }
+            Some(cursor) => {
+                let (generation, offset) = signer
+                    .decode(self.list, scope.session, cursor)
+                    .ok_or_else(invalid_cursor)?;
+                let items = self.load(scope, generation).ok_or_else(invalid_cursor)?;
+                (generation, offset, items)
+            }
+        };
+
+        let start = usize::try_from(offset).map_err(|_| invalid_cursor())?;
+        if start > items.len() {
+            return Err(invalid_cursor());
+        }
+        let end = items.len().min(start.saturating_add(page_size));
+        let next_cursor = (end < items.len())
+            .then(|| signer.encode(self.list, scope.session, generation, end as u64));
+        Ok((items[start..end].to_vec(), next_cursor))
+    }
+}
+
+// =============================================================================
+// Provider implementations
+// =============================================================================
+
+impl<P: ToolsProvider> ToolsProvider for Paginated<P> {
+    async fn list_tools(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        let scope = Scope::new(&context);
+        if let Some(caches) = CacheHooks::from_context(&context) {
+            caches.session_bound_cursors();
+        }
+        let (meta, cursor) = request.map(|r| (r.meta, r.cursor)).unwrap_or_default();
+        let inner = &self.inner;
+        let (tools, next_cursor) = self
+            .tools
+            .page(
+                &self.signer,
+                self.page_size,
+                scope,
+                cursor.as_deref(),
+                || {
+                    fetch_all(meta, |params| {
+                        let context = context.clone();
+                        async move {
+                            let result = inner.list_tools(Some(params), context).await?;
+                            Ok((result.tools, result.next_cursor))
+                        }
+                    })
+                },
+            )
+            .await?;
+        Ok(ListToolsResult {
+            meta: None,
+            next_cursor,
+            tools,
+        })
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.inner.call_tool(request, context).await
+    }
+}
+
+impl<P: PromptsProvider> PromptsProvider for Paginated<P> {
+    async fn list_prompts(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, ErrorData> {
+        let scope = Scope::new(&context);
+        if let Some(caches) = CacheHooks::from_context(&context) {
+            caches.session_bound_cursors();
+        }
+        let (meta, cursor) = request.map(|r| (r.meta, r.cursor)).unwrap_or_default();
+        let inner = &self.inner;
+        let (prompts, next_cursor) = self
+            .prompts
+            .page(
+                &self.signer,
+                self.page_size,
+                scope,
+                cursor.as_deref(),
+                || {
+                    fetch_all(meta, |params| {
+                        let context = context.clone();
+                        async move {
+                            let result = inner.list_prompts(Some(params), context).await?;
+                            Ok((result.prompts, result.next_cursor))
+                        }
+                    })
+                },
+            )
+            .await?;
+        Ok(ListPromptsResult {
+            meta: None,
+            next_cursor,
+            prompts,
+        })
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, ErrorData> {
+        self.inner.get_prompt(request, context).await
+    }
+}
+
+impl<P: ResourcesProvider> ResourcesProvider for Paginated<P> {
+    async fn list_resources(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, ErrorData> {
+        let scope = Scope::new(&context);
+        if let Some(caches) = CacheHooks::from_context(&context) {
+            caches.session_bound_cursors();
+        }
+        let (meta, cursor) = request.map(|r| (r.meta, r.cursor)).unwrap_or_default();
+        let inner = &self.inner;
+        let (resources, next_cursor) = self
+            .resources
+            .page(
+                &self.signer,
+                self.page_size,
+                scope,
+                cursor.as_deref(),
+                || {
+                    fetch_all(meta, |params| {
+                        let context = context.clone();
+                        async move {
+                            let result = inner.list_resources(Some(params), context).await?;
+                            Ok((result.resources, result.next_cursor))
+                        }
+                    })
+                },
+            )
+            .await?;
+        Ok(ListResourcesResult {
+            meta: None,
+            next_cursor,
+            resources,
+        })
+    }
+
+    async fn list_resource_templates(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, ErrorData> {
+        let scope = Scope::new(&context);
+        if let Some(caches) = CacheHooks::from_context(&context) {
+            caches.session_bound_cursors();
+        }
+        let (meta, cursor) = request.map(|r| (r.meta, r.cursor)).unwrap_or_default();
+        let inner = &self.inner;
+        let (resource_templates, next_cursor) = self
+            .resource_templates
+            .page(
+                &self.signer,
+                self.page_size,
+                scope,
+                cursor.as_deref(),
+                || {
+                    fetch_all(meta, |params| {
+                        let context = context.clone();
+                        async move {
+                            let result =
+                                inner.list_resource_templates(Some(params), context).await?;
+                            Ok((result.resource_templates, result.next_cursor))
+                        }
+                    })
+                },
+            )
+            .await?;
+        Ok(ListResourceTemplatesResult {
+            meta: None,
+            next_cursor,
+            resource_templates,
+        })
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, ErrorData> {
+        self.inner.read_resource(request, context).await
+    }
+
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        self.inner.subscribe(request, context).await
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        self.inner.unsubscribe(request, context).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::ErrorCode;
+
+    async fn items(count: u32) -> Result<Vec<u32>, ErrorData> {
+        Ok((0..count).collect())
+    }
+
+    fn scope(session: Option<SessionId>) -> Scope<'static> {
+        Scope {
+            session,
+            state: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pages_through_snapshot() {
+        let signer = CursorSigner::new();
+        let cache = PageCache::new("test");
+
+        let (page, cursor) = cache
+            .page(&signer, 2, scope(None), None, || items(5))
+            .await
+            .unwrap();
+        assert_eq!(page, vec![0, 1]);
+        let cursor = cursor.unwrap();
+
+        let (page, cursor) = cache
+            .page(&signer, 2, scope(None), Some(&cursor), || items(0))
+            .await
+            .unwrap();
+        assert_eq!(page, vec![2, 3]);
+        let cursor = cursor.unwrap();
+
+        let (page, cursor) = cache
+            .page(&signer, 2, scope(None), Some(&cursor), || items(0))
+            .await
+            .unwrap();
+        assert_eq!(page, vec![4]);
+        assert!(cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_tampered_cursor() {
+        let signer = CursorSigner::new();
+        let cache = PageCache::new("test");
+
+        let (_, cursor) = cache
+            .page(&signer, 2, scope(None), None, || items(5))
+            .await
+            .unwrap();
+        let cursor = cursor.unwrap();
+        let mut tampered = cursor.clone();
+        tampered.replace_range(16..32, &format!("{:016x}", 4));
+
+        let err = cache
+            .page(&signer, 2, scope(None), Some(&tampered), || items(0))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_cursor_is_bound_to_session() {
+        let signer = CursorSigner::new();
+        let cache = PageCache::new("test");
+        let session = Some(SessionId::next());
+
+        let (_, cursor) = cache
+            .page(&signer, 2, scope(session), None, || items(5))
+            .await
+            .unwrap();
+        let cursor = cursor.unwrap();
+
+        assert!(
+            cache
+                .page(
+                    &signer,
+                    2,
+                    scope(Some(SessionId::next())),
+                    Some(&cursor),
+                    || items(0)
+                )
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_new_listing_expires_old_cursor() {
+        let signer = CursorSigner::new();
+        let cache = PageCache::new("test");
+
+        let (_, cursor) = cache
+            .page(&signer, 2, scope(None), None, || items(5))
+            .await
+            .unwrap();
+        cache
+            .page(&signer, 2, scope(None), None, || items(5))
+            .await
+            .unwrap();
+
+        assert!(
+            cache
+                .page(&signer, 2, scope(None), cursor.as_deref(), || items(0))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_lives_in_session_state() {
+        let signer = CursorSigner::new();
+        let cache = PageCache::new("test");
+        let state = SessionState::default();
+        let scope = Scope {
+            session: Some(SessionId::next()),
+            state: Some(&state),
+        };
+
+        let (_, cursor) = cache
+            .page(&signer, 2, scope, None, || items(5))
+            .await
+            .unwrap();
+        assert!(cache.snapshots.lock().unwrap().is_empty());
+        let (page, _) = cache
+            .page(&signer, 2, scope, cursor.as_deref(), || items(0))
+            .await
+            .unwrap();
+        assert_eq!(page, vec![2, 3]);
+
+        // Ending the session drops its snapshots.
+        state.clear();
+        assert!(
+            cache
+                .page(&signer, 2, scope, cursor.as_deref(), || items(0))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_all_stops_on_runaway_upstream() {
+        let repeating = fetch_all(None, |params| async move {
+            let next = params.cursor.map_or(1, |cursor| cursor.len() % 2 + 1);
+            Ok((vec![0u32], Some("x".repeat(next))))
+        })
+        .await
+        .unwrap_err();
+        assert!(repeating.message.contains("repeated cursor"));
+
+        let endless = fetch_all(None, |params| async move {
+            let next = params.cursor.map_or(0, |cursor| cursor.len()) + 1;
+            Ok((Vec::<u32>::new(), Some("x".repeat(next))))
+        })
+        .await
+        .unwrap_err();
+        assert!(endless.message.contains("more than"));
+    }
+}
@@ -0,0 +1,714 @@
+//! Providers forwarding to a remote MCP server.
+//!
+//! [`ProxyProvider`] runs an rmcp client session against another MCP server, reached
+//! over any rmcp client transport (child process stdio, streamable HTTP, ...), and
+//! implements the tools, prompts, resources and completion provider traits by
+//! forwarding requests to it. List changes and resource updates announced by the
+//! remote server are forwarded to the sessions of the composed server.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rmcp::{
+    ClientHandler, Peer, RoleClient, RoleServer, ServiceExt,
+    model::{
+        CallToolRequestParams, CallToolResult, ClientInfo, ClientRequest, CompleteRequestParams,
+        CompleteResult, ErrorData, GetPromptRequestParams, GetPromptResult, Implementation,
+        ListPromptsResult, ListResourceTemplatesResult, ListResourcesResult, ListToolsResult,
+        PaginatedRequestParams, PingRequest, ReadResourceRequestParams, ReadResourceResult,
+        ResourceUpdatedNotificationParam, ServerInfo, SubscribeRequestParams,
+        UnsubscribeRequestParams,
+    },
+    service::{
+        ClientInitializeError, NotificationContext, PeerRequestOptions, RequestContext,
+        RunningService, ServiceError,
+    },
+    transport::IntoTransport,
+};
+
+use crate::cache::CacheHooks;
+use crate::providers::{CompletionProvider, PromptsProvider, ResourcesProvider, ToolsProvider};
+use crate::session::SessionId;
+
+/// Convert an error of the remote session into an error for the local client.
+///
+/// Errors returned by the remote server are passed through unchanged.
+pub(crate) fn remote_error(error: ServiceError) -> ErrorData {
+    match error {
+        ServiceError::McpError(error) => error,
+        error => ErrorData::internal_error(format!("remote MCP server: {error}"), None),
+    }
+}
+
+// =============================================================================
+// Upstream sessions
+// =============================================================================
+
+#[derive(Debug)]
+struct Session {
+    peer: Peer<RoleServer>,
+    subscriptions: HashSet<String>,
+}
+
+/// Sessions of the composed server that receive notifications from a remote server,
+/// and caches that must be invalidated by them.
+#[derive(Debug, Default)]
+pub(crate) struct Upstream {
+    sessions: Mutex<HashMap<SessionId, Session>>,
+    caches: Mutex<CacheHooks>,
+    /// Locks serializing the subscription changes to each URI.
+    uris: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl Upstream {
+    /// Remember the session of a request so it receives forwarded notifications, and the
+    /// caches the request went through so they are invalidated by them.
+    pub(crate) fn register(&self, context: &RequestContext<RoleServer>) {
+        if let Some(hooks) = CacheHooks::from_context(context) {
+            self.caches.lock().unwrap().merge(hooks);
+        }
+        let Some(session) = SessionId::from_context(context) else {
+            return;
+        };
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|_, session| !session.peer.is_transport_closed());
+        sessions.entry(session).or_insert_with(|| Session {
+            peer: context.peer.clone(),
+            subscriptions: HashSet::new(),
+        });
+    }
+
+    /// Wait for the subscription changes to `uri` in progress, and hold off the next
+    /// ones until the returned guard is dropped.
+    ///
+    /// A session subscribing while the first subscription to `uri` is still being
+    /// forwarded thereby learns whether the remote server accepted it.
+    async fn lock(&self, uri: &str) -> tokio::sync::OwnedMutexGuard<()> {
+        let lock = {
+            let mut uris = self.uris.lock().unwrap();
+            uris.retain(|_, lock| Arc::strong_count(lock) > 1);
+            uris.entry(uri.to_owned()).or_default().clone()
+        };
+        lock.lock_owned().await
+    }
+
+    /// Record a subscription, returning whether it is the first one to `uri`.
+    fn subscribe(&self, context: &RequestContext<RoleServer>, uri: &str) -> bool {
+        let Some(id) = SessionId::from_context(context) else {
+            return true;
+        };
+        let mut sessions = self.sessions.lock().unwrap();
+        let first = !sessions.values().any(|s| s.subscriptions.contains(uri));
+        if let Some(session) = sessions.get_mut(&id) {
+            session.subscriptions.insert(uri.to_owned());
+        }
+        first
+    }
+
+    /// Drop a subscription, returning whether no session is subscribed to `uri` anymore.
+    fn unsubscribe(&self, context: &RequestContext<RoleServer>, uri: &str) -> bool {
+        let Some(id) = SessionId::from_context(context) else {
+            return true;
+        };
+        let mut sessions = self.sessions.lock().unwrap();
+        if let Some(session) = sessions.get_mut(&id) {
+            session.subscriptions.remove(uri);
+        }
+        !sessions.values().any(|s| s.subscriptions.contains(uri))
+    }
+
+    /// Whether the session of a request is subscribed to `uri`.
+    pub(crate) fn is_subscribed(&self, context: &RequestContext<RoleServer>, uri: &str) -> bool {
+        let Some(id) = SessionId::from_context(context) else {
+            return false;
+        };
+        let sessions = self.sessions.lock().unwrap();
+        sessions
+            .get(&id)
+            .is_some_and(|session| session.subscriptions.contains(uri))
+    }
+
+    /// Get the caches to invalidate when forwarding notifications.
+    fn caches(&self) -> CacheHooks {
+        self.caches.lock().unwrap().clone()
+    }
+
+    /// URIs at least one session is subscribed to.
+    fn subscriptions(&self) -> HashSet<String> {
+        let sessions = self.sessions.lock().unwrap();
+        sessions
+            .values()
+            .flat_map(|session| session.subscriptions.iter().cloned())
+            .collect()
+    }
+
+    /// Peers of live sessions, optionally only those subscribed to `uri`.
+    fn peers(&self, uri: Option<&str>) -> Vec<Peer<RoleServer>> {
+        let sessions = self.sessions.lock().unwrap();
+        sessions
+            .values()
+            .filter(|session| !session.peer.is_transport_closed())
+            .filter(|session| uri.is_none_or(|uri| session.subscriptions.contains(uri)))
+            .map(|session| session.peer.clone())
+            .collect()
+    }
+}
+
+/// Client handler forwarding notifications of a remote server to upstream sessions.
+#[derive(Clone, Debug)]
+pub(crate) struct ProxyClient {
+    pub(crate) upstream: Arc<Upstream>,
+}
+
+impl ClientHandler for ProxyClient {
+    fn get_info(&self) -> ClientInfo {
+        ClientInfo {
+            client_info: Implementation {
+                name: env!("CARGO_PKG_NAME").to_owned(),
+                version: env!("CARGO_PKG_VERSION").to_owned(),
+                ..Implementation::default()
+            },
+            ..ClientInfo::default()
+        }
+    }
+
+    // Caches are invalidated before sessions are notified, so clients listing again
+    // see the changes. Failed notifications only affect the session they were sent to.
+
+    async fn on_tool_list_changed(&self, _context: NotificationContext<RoleClient>) {
+        self.upstream.caches().tool_list_changed();
+        for peer in self.upstream.peers(None) {
+            let _ = peer.notify_tool_list_changed().await;
+        }
+    }
+
+    async fn on_prompt_list_changed(&self, _context: NotificationContext<RoleClient>) {
+        self.upstream.caches().prompt_list_changed();
+        for peer in self.upstream.peers(None) {
+            let _ = peer.notify_prompt_list_changed().await;
+        }
+    }
+
+    async fn on_resource_list_changed(&self, _context: NotificationContext<RoleClient>) {
+        self.upstream.caches().resource_list_changed();
+        for peer in self.upstream.peers(None) {
+            let _ = peer.notify_resource_list_changed().await;
+        }
+    }
+
+    async fn on_resource_updated(
+        &self,
+        params: ResourceUpdatedNotificationParam,
+        _context: NotificationContext<RoleClient>,
+    ) {
+        self.upstream.caches().resource_updated(&params.uri);
+        for peer in self.upstream.peers(Some(&params.uri)) {
+            let _ = peer.notify_resource_updated(params.clone()).await;
+        }
+    }
+}
+
+// =============================================================================
+// Provider
+// =============================================================================
+
+#[derive(Debug)]
+struct Remote {
+    service: RunningService<RoleClient, ProxyClient>,
+    upstream: Arc<Upstream>,
+}
+
+/// Provider forwarding requests to a remote MCP server.
+///
+/// Clones share the same remote session, which is closed when the last clone is dropped.
+///
+/// # Example
+///
+/// ```no_run
+/// # #[cfg(feature = "child-process")]
+/// # async fn compose(info: rmcp::model::Implementation) -> Result<(), Box<dyn std::error::Error>> {
+/// use rmcp::transport::TokioChildProcess;
+/// use rmcp_server_builder::{ProxyProvider, ServerBuilder};
+/// use tokio::process::Command;
+///
+/// let remote = ProxyProvider::connect(TokioChildProcess::new(Command::new("mcp-git"))?).await?;
+/// let server = ServerBuilder::new()
+///     .info(info)
+///     .tools(remote.clone())
+///     .resources(remote)
+///     .build();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ProxyProvider {
+    remote: Arc<Remote>,
+}
+
+impl ProxyProvider {
+    /// Connect to a remote MCP server over a client transport.
+    pub async fn connect<T, E, A>(transport: T) -> Result<Self, ClientInitializeError>
+    where
+        T: IntoTransport<RoleClient, E, A>,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        Self::connect_with(transport, Arc::new(Upstream::default())).await
+    }
+
+    /// Connect to a remote MCP server on behalf of already known upstream sessions.
+    ///
+    /// Used when reconnecting: subscriptions of the upstream sessions are renewed on the
+    /// new remote session.
+    pub(crate) async fn connect_with<T, E, A>(
+        transport: T,
+        upstream: Arc<Upstream>,
+    ) -> Result<Self, ClientInitializeError>
+    where
+        T: IntoTransport<RoleClient, E, A>,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        let client = ProxyClient {
+            upstream: upstream.clone(),
+        };
+        let service = client.serve(transport).await?;
+        let proxy = Self {
+            remote: Arc::new(Remote { service, upstream }),
+        };
+        for uri in proxy.remote.upstream.subscriptions() {
+            // A resource that vanished meanwhile just stops sending updates.
+            let _ = proxy
+                .peer()
+                .subscribe(SubscribeRequestParams { meta: None, uri })
+                .await;
+        }
+        Ok(proxy)
+    }
+
+    /// Get the client peer of the remote session.
+    pub fn peer(&self) -> &Peer<RoleClient> {
+        self.remote.service.peer()
+    }
+
+    /// Get the information the remote server sent during initialization.
+    pub fn server_info(&self) -> Option<&ServerInfo> {
+        self.peer().peer_info()
+    }
+
+    /// Check whether the remote session is closed.
+    pub fn is_closed(&self) -> bool {
+        self.remote.service.is_closed() || self.peer().is_transport_closed()
+    }
+
+    /// Check that the remote server answers a ping within `timeout`.
+    pub async fn ping(&self, timeout: Duration) -> Result<(), ServiceError> {
+        let request = ClientRequest::PingRequest(PingRequest::default());
+        let options = PeerRequestOptions {
+            timeout: Some(timeout),
+            meta: None,
+        };
+        self.peer()
+            .send_request_with_option(request, options)
+            .await?
+            .await_response()
+            .await?;
+        Ok(())
+    }
+
+    fn forward(&self, context: &RequestContext<RoleServer>) -> &Peer<RoleClient> {
+        self.remote.upstream.register(context);
+        self.peer()
+    }
+}
+
+impl ToolsProvider for ProxyProvider {
+    async fn list_tools(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        let peer = self.forward(&context);
+        peer.list_tools(request).await.map_err(remote_error)
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let peer = self.forward(&context);
+        peer.call_tool(request).await.map_err(remote_error)
+    }
+}
+
+impl PromptsProvider for ProxyProvider {
+    async fn list_prompts(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, ErrorData> {
+        let peer = self.forward(&context);
+        peer.list_prompts(request).await.map_err(remote_error)
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, ErrorData> {
+        let peer = self.forward(&context);
+        peer.get_prompt(request).await.map_err(remote_error)
+    }
+}
+
+impl ResourcesProvider for ProxyProvider {
+    async fn list_resources(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, ErrorData> {
+        let peer = self.forward(&context);
+        peer.list_resources(request).await.map_err(remote_error)
+    }
+
+    async fn list_resource_templates(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, ErrorData> {
+        let peer = self.forward(&context);
+        peer.list_resource_templates(request)
+            .await
+            .map_err(remote_error)
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, ErrorData> {
+        let peer = self.forward(&context);
+        peer.read_resource(request).await.map_err(remote_error)
+    }
+
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        let peer = self.forward(&context);
+        let uri = request.uri.clone();
+        let _guard = self.remote.upstream.lock(&uri).await;
+        if self.remote.upstream.subscribe(&context, &uri)
+            && let Err(error) = peer.subscribe(request).await
+        {
+            // The remote server sends no updates, so the session is not subscribed.
+            self.remote.upstream.unsubscribe(&context, &uri);
+            return Err(remote_error(error));
+        }
+        Ok(())
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        let peer = self.forward(&context);
+        let _guard = self.remote.upstream.lock(&request.uri).await;
+        if self.remote.upstream.unsubscribe(&context, &request.uri) {
+            peer.unsubscribe(request).await.map_err(remote_error)?;
+        }
+        Ok(())
+    }
+}
+
+impl CompletionProvider for ProxyProvider {
+    async fn complete(
+        &self,
+        request: CompleteRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CompleteResult, ErrorData> {
+        let peer = self.forward(&context);
+        peer.complete(request).await.map_err(remote_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rmcp::model::{Content, ErrorCode, ResourceContents, Tool};
+
+    use super::*;
+    use crate::test_support::{self, call};
+    use crate::{CacheConfig, ListCache, ResultCache, ServerBuilder};
+
+    struct Echo;
+
+    impl ToolsProvider for Echo {
+        async fn list_tools(
+            &self,
+            _request: Option<PaginatedRequestParams>,
+            _context: RequestContext<RoleServer>,
+        ) -> Result<ListToolsResult, ErrorData> {
+            let tool = Tool::new("echo", "Echo the input", Arc::new(Default::default()));
+            Ok(ListToolsResult::with_all_items(vec![tool]))
+        }
+
+        async fn call_tool(
+            &self,
+            request: CallToolRequestParams,
+            _context: RequestContext<RoleServer>,
+        ) -> Result<CallToolResult, ErrorData> {
+            match request.name.as_ref() {
+                "echo" => Ok(CallToolResult::success(vec![Content::text("hello")])),
+                _ => Err(ErrorData::invalid_params("unknown tool", None)),
+            }
+        }
+    }
+
+    /// Remote provider whose tools and resource contents change over time.
+    #[derive(Clone, Default)]
+    struct Changing {
+        version: Arc<Mutex<u32>>,
+        peer: Arc<Mutex<Option<Peer<RoleServer>>>>,
+    }
+
+    impl Changing {
+        fn bump(&self) -> Peer<RoleServer> {
+            *self.version.lock().unwrap() += 1;
+            self.peer.lock().unwrap().clone().unwrap()
+        }
+
+        fn observe(&self, context: &RequestContext<RoleServer>) -> u32 {
+            *self.peer.lock().unwrap() = Some(context.peer.clone());
+            *self.version.lock().unwrap()
+        }
+    }
+
+    impl ToolsProvider for Changing {
+        async fn list_tools(
+            &self,
+            _request: Option<PaginatedRequestParams>,
+            context: RequestContext<RoleServer>,
+        ) -> Result<ListToolsResult, ErrorData> {
+            let version = self.observe(&context);
+            let tools = (0..=version)
+                .map(|i| Tool::new(format!("tool_{i}"), "", Arc::new(Default::default())))
+                .collect();
+            Ok(ListToolsResult::with_all_items(tools))
+        }
+
+        async fn call_tool(
+            &self,
+            _request: CallToolRequestParams,
+            _context: RequestContext<RoleServer>,
+        ) -> Result<CallToolResult, ErrorData> {
+            Err(ErrorData::invalid_params("unknown tool", None))
+        }
+    }
+
+    impl ResourcesProvider for Changing {
+        async fn list_resources(
+            &self,
+            _request: Option<PaginatedRequestParams>,
+            _context: RequestContext<RoleServer>,
+        ) -> Result<ListResourcesResult, ErrorData> {
+            Ok(ListResourcesResult::default())
+        }
+
+        async fn list_resource_templates(
+            &self,
+            _request: Option<PaginatedRequestParams>,
+            _context: RequestContext<RoleServer>,
+        ) -> Result<ListResourceTemplatesResult, ErrorData> {
+            Ok(ListResourceTemplatesResult::default())
+        }
+
+        async fn read_resource(
+            &self,
+            request: ReadResourceRequestParams,
+            context: RequestContext<RoleServer>,
+        ) -> Result<ReadResourceResult, ErrorData> {
+            let version = self.observe(&context);
+            Ok(ReadResourceResult {
+                contents: vec![ResourceContents::text(format!("v{version}"), request.uri)],
+            })
+        }
+
+        async fn subscribe(
+            &self,
+            request: SubscribeRequestParams,
+            _context: RequestContext<RoleServer>,
+        ) -> Result<(), ErrorData> {
+            match request.uri.starts_with("private://") {
+                true => {
+                    // Slow enough for concurrent subscriptions to overlap.
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    Err(ErrorData::invalid_params("not subscribable", None))
+                }
+                false => Ok(()),
+            }
+        }
+
+        async fn unsubscribe(
+            &self,
+            _request: UnsubscribeRequestParams,
+            _context: RequestContext<RoleServer>,
+        ) -> Result<(), ErrorData> {
+            Ok(())
+        }
+    }
+
+    fn info(name: &str) -> Implementation {
+        Implementation {
+            name: name.into(),
+            version: "1.0.0".into(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_forwarding() {
+        let remote = ServerBuilder::new()
+            .info(info("remote"))
+            .tools(Echo)
+            .build();
+        let proxy = ProxyProvider::connect(test_support::serve(remote))
+            .await
+            .unwrap();
+        assert_eq!(proxy.server_info().unwrap().server_info.name, "remote");
+
+        let server = ServerBuilder::new()
+            .info(info("proxy"))
+            .tools(proxy)
+            .build();
+        let client = test_support::connect(server, ()).await;
+
+        let tools = client.list_all_tools().await.unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, "echo");
+        let result = client.call_tool(call("echo")).await.unwrap();
+        assert_eq!(result.is_error, Some(false));
+        let error = client.call_tool(call("missing")).await.unwrap_err();
+        assert!(matches!(
+            error,
+            ServiceError::McpError(ErrorData { code, .. }) if code == ErrorCode::INVALID_PARAMS
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_forwarded_notifications_invalidate_caches() {
+        let changing = Changing::default();
+        let remote = ServerBuilder::new()
+            .info(info("remote"))
+            .tools(changing.clone())
+            .resources(changing.clone())
+            .build();
+        let proxy = ProxyProvider::connect(test_support::serve(remote))
+            .await
+            .unwrap();
+
+        let server = ServerBuilder::new()
+            .info(info("proxy"))
+            .tools(proxy.clone())
+            .resources(proxy)
+            .cache_resources(ResultCache::new(CacheConfig::new()))
+            .cache_lists(ListCache::new(CacheConfig::new()))
+            .build();
+        let client = test_support::connect(server, ()).await;
+        let read = || {
+            client.read_resource(ReadResourceRequestParams {
+                meta: None,
+                uri: "doc://a".into(),
+            })
+        };
+        let text = |result: ReadResourceResult| match &result.contents[0] {
+            ResourceContents::TextResourceContents { text, .. } => text.clone(),
+            contents => panic!("unexpected contents: {contents:?}"),
+        };
+
+        assert_eq!(client.list_all_tools().await.unwrap().len(), 1);
+        assert_eq!(text(read().await.unwrap()), "v0");
+
+        // Cached until the remote server announces the changes.
+        let remote_peer = changing.bump();
+        assert_eq!(client.list_all_tools().await.unwrap().len(), 1);
+        assert_eq!(text(read().await.unwrap()), "v0");
+
+        remote_peer.notify_tool_list_changed().await.unwrap();
+        remote_peer
+            .notify_resource_updated(ResourceUpdatedNotificationParam {
+                uri: "doc://a".into(),
+            })
+            .await
+            .unwrap();
+        let mut fresh = (0, String::new());
+        for _ in 0..100 {
+            fresh = (
+                client.list_all_tools().await.unwrap().len(),
+                text(read().await.unwrap()),
+            );
+            if fresh == (2, "v1".to_owned()) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(fresh, (2, "v1".to_owned()));
+    }
+
+    #[tokio::test]
+    async fn test_failed_subscription_is_not_recorded() {
+        let remote = ServerBuilder::new()
+            .info(info("remote"))
+            .resources(Changing::default())
+            .build();
+        let proxy = ProxyProvider::connect(test_support::serve(remote))
+            .await
+            .unwrap();
+
+        let server = ServerBuilder::new()
+            .info(info("proxy"))
+            .resources(proxy.clone())
+            .build();
+        let client = test_support::connect(server, ()).await;
+        let subscribe = |uri: &str| {
+            client.subscribe(SubscribeRequestParams {
+                meta: None,
+                uri: uri.into(),
+            })
+        };
+
+        assert!(subscribe("private://a").await.is_err());
+        subscribe("doc://a").await.unwrap();
+        assert_eq!(
+            proxy.remote.upstream.subscriptions(),
+            HashSet::from(["doc://a".to_owned()])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_subscriptions_wait_for_the_remote_answer() {
+        let remote = ServerBuilder::new()
+            .info(info("remote"))
+            .resources(Changing::default())
+            .build();
+        let proxy = ProxyProvider::connect(test_support::serve(remote))
+            .await
+            .unwrap();
+        let server = ServerBuilder::new()
+            .info(info("proxy"))
+            .resources(proxy.clone())
+            .build();
+        let first = test_support::connect(server.clone(), ()).await;
+        let second = test_support::connect(server, ()).await;
+        let request = || SubscribeRequestParams {
+            meta: None,
+            uri: "private://a".into(),
+        };
+
+        let (first, second) = tokio::join!(first.subscribe(request()), second.subscribe(request()));
+        assert!(first.is_err());
+        assert!(second.is_err());
+        assert!(proxy.remote.upstream.subscriptions().is_empty());
+    }
+}
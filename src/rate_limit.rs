@@ -0,0 +1,481 @@
+//! Rate limiting and concurrency caps for tool calls.
+//!
+//! [`RateLimited`] wraps a tools provider and enforces the [`RateLimits`] configured
+//! per tool, per session, or per tool within each session. Calls exceeding a limit are
+//! rejected with a [`RATE_LIMITED`] error carrying a `retryAfterMs` hint: the time
+//! until a token is available for rate limits, and the average duration of recent
+//! calls in the same scope for concurrency caps.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rmcp::{
+    model::{
+        CallToolRequestParams, CallToolResult, ErrorCode, ErrorData, ListToolsResult,
+        PaginatedRequestParams,
+    },
+    service::{RequestContext, RoleServer},
+};
+use serde_json::json;
+
+use crate::alias::AliasChain;
+use crate::providers::ToolsProvider;
+use crate::session::SessionId;
+
+/// Error code returned when a call exceeds a rate or concurrency limit.
+pub const RATE_LIMITED: ErrorCode = ErrorCode(-32029);
+
+/// Number of tracked limiter entries above which idle entries are dropped.
+const PRUNE_THRESHOLD: usize = 4096;
+
+/// Retry hint for concurrency rejections in a scope where no call has completed yet.
+const DEFAULT_CONCURRENCY_RETRY: Duration = Duration::from_millis(100);
+
+/// A token-bucket rate: `requests` per `period`, with bursts of up to `burst` requests.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rate {
+    burst: u32,
+    interval: Duration,
+}
+
+impl Rate {
+    /// Allow `requests` per `period`, with bursts of up to `requests`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `requests` or `period` is zero.
+    pub fn new(requests: u32, period: Duration) -> Self {
+        assert!(requests > 0, "rate must allow at least one request");
+        assert!(!period.is_zero(), "rate period must be longer than zero");
+        Self {
+            burst: requests,
+            interval: period / requests,
+        }
+    }
+
+    /// Allow `requests` per second.
+    pub fn per_second(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(1))
+    }
+
+    /// Allow `requests` per minute.
+    pub fn per_minute(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(60))
+    }
+
+    /// Set the maximum number of requests allowed in a burst.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `burst` is zero.
+    pub fn burst(mut self, burst: u32) -> Self {
+        assert!(burst > 0, "burst must allow at least one request");
+        self.burst = burst;
+        self
+    }
+}
+
+/// Limits applied to one scope: a token-bucket rate and/or a concurrency cap.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Quota {
+    rate: Option<Rate>,
+    max_concurrency: Option<usize>,
+}
+
+impl Quota {
+    /// Create a quota without limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the rate of calls.
+    pub fn rate(mut self, rate: Rate) -> Self {
+        self.rate = Some(rate);
+        self
+    }
+
+    /// Limit the number of calls in flight at the same time.
+    pub fn max_concurrency(mut self, max: usize) -> Self {
+        self.max_concurrency = Some(max);
+        self
+    }
+}
+
+/// Rate and concurrency limits for tool calls.
+///
+/// A call must satisfy every quota that applies to it: the quota of the tool (shared
+/// by all sessions), the quota of the calling session (shared by all tools) and the
+/// quota of the tool within the calling session.
+///
+/// # Example
+///
+/// ```
+/// use rmcp_server_builder::{Quota, Rate, RateLimits};
+///
+/// let limits = RateLimits::new()
+///     .tool("render", Quota::new().rate(Rate::per_minute(60)).max_concurrency(4))
+///     .per_session(Quota::new().rate(Rate::per_second(10)))
+///     .per_session_tool("render", Quota::new().max_concurrency(1));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct RateLimits {
+    tools: HashMap<String, Quota>,
+    session: Option<Quota>,
+    session_tools: HashMap<String, Quota>,
+}
+
+impl RateLimits {
+    /// Create limits that allow every call.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit calls of a tool across all sessions.
+    pub fn tool(mut self, name: impl Into<String>, quota: Quota) -> Self {
+        self.tools.insert(name.into(), quota);
+        self
+    }
+
+    /// Limit calls of all tools within each session.
+    pub fn per_session(mut self, quota: Quota) -> Self {
+        self.session = Some(quota);
+        self
+    }
+
+    /// Limit calls of a tool within each session.
+    pub fn per_session_tool(mut self, name: impl Into<String>, quota: Quota) -> Self {
+        self.session_tools.insert(name.into(), quota);
+        self
+    }
+}
+
+// =============================================================================
+// Limiter state
+// =============================================================================
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Scope {
+    Tool(String),
+    Session(Option<SessionId>),
+    SessionTool(Option<SessionId>, String),
+}
+
+impl Scope {
+    fn describe(&self) -> &'static str {
+        match self {
+            Scope::Tool(_) => "tool",
+            Scope::Session(_) => "session",
+            Scope::SessionTool(..) => "session_tool",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    rate: Rate,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn is_full(&self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.updated);
+        self.tokens + elapsed.as_secs_f64() / self.rate.interval.as_secs_f64()
+            >= self.rate.burst as f64
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated);
+        let refilled = elapsed.as_secs_f64() / self.rate.interval.as_secs_f64();
+        self.tokens = (self.tokens + refilled).min(self.rate.burst as f64);
+        self.updated = now;
+    }
+
+    /// Time until a token is available, or `None` if one is available now.
+    fn wait_time(&self) -> Option<Duration> {
+        (self.tokens < 1.0).then(|| self.rate.interval.mul_f64(1.0 - self.tokens))
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    bucket: Option<Bucket>,
+    in_flight: usize,
+    /// Moving average of the duration of completed calls.
+    call_time: Option<Duration>,
+}
+
+impl Entry {
+    fn record_call(&mut self, duration: Duration) {
+        self.call_time = Some(match self.call_time {
+            Some(average) => (average * 3 + duration) / 4,
+            None => duration,
+        });
+    }
+}
+
+#[derive(Debug, Default)]
+struct Limiter {
+    entries: Mutex<HashMap<Scope, Entry>>,
+}
+
+/// A call that was admitted; releases its concurrency slots when dropped.
+struct Permit {
+    limiter: Arc<Limiter>,
+    scopes: Vec<Scope>,
+    started: Instant,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let duration = self.started.elapsed();
+        let mut entries = self.limiter.entries.lock().unwrap();
+        for scope in &self.scopes {
+            if let Some(entry) = entries.get_mut(scope) {
+                entry.in_flight = entry.in_flight.saturating_sub(1);
+                entry.record_call(duration);
+            }
+        }
+    }
+}
+
+impl Limiter {
+    /// Admit a call if every applicable quota allows it.
+    fn acquire(
+        self: &Arc<Self>,
+        quotas: &[(Scope, Quota)],
+        now: Instant,
+    ) -> Result<Permit, ErrorData> {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() > PRUNE_THRESHOLD {
+            // Idle entries with a full bucket hold no state worth keeping.
+            entries.retain(|_, entry| {
+                entry.in_flight > 0 || entry.bucket.as_ref().is_some_and(|b| !b.is_full(now))
+            });
+        }
+
+        // Check every quota first, so a rejected call consumes nothing.
+        for (scope, quota) in quotas {
+            let entry = entries.entry(scope.clone()).or_insert_with(|| Entry {
+                bucket: quota.rate.map(|rate| Bucket {
+                    rate,
+                    tokens: rate.burst as f64,
+                    updated: now,
+                }),
+                in_flight: 0,
+                call_time: None,
+            });
+            if let Some(max) = quota.max_concurrency
+                && entry.in_flight >= max
+            {
+                let wait = entry.call_time.unwrap_or(DEFAULT_CONCURRENCY_RETRY);
+                return Err(ErrorData::new(
+                    RATE_LIMITED,
+                    "too many concurrent calls",
+                    Some(json!({
+                        "scope": scope.describe(),
+                        "maxConcurrency": max,
+                        "retryAfterMs": wait.as_millis() as u64 + 1,
+                    })),
+                ));
+            }
+            if let Some(bucket) = &mut entry.bucket {
+                bucket.refill(now);
+                if let Some(wait) = bucket.wait_time() {
+                    return Err(ErrorData::new(
+                        RATE_LIMITED,
+                        "rate limit exceeded",
+                        Some(json!({
+                            "scope": scope.describe(),
+                            "retryAfterMs": wait.as_millis() as u64 + 1,
+                        })),
+                    ));
+                }
+            }
+        }
+
+        for (scope, _) in quotas {
+            if let Some(entry) = entries.get_mut(scope) {
+                if let Some(bucket) = &mut entry.bucket {
+                    bucket.tokens -= 1.0;
+                }
+                entry.in_flight += 1;
+            }
+        }
+
+        Ok(Permit {
+            limiter: self.clone(),
+            scopes: quotas.iter().map(|(scope, _)| scope.clone()).collect(),
+            started: now,
+        })
+    }
+}
+
+// =============================================================================
+// Provider
+// =============================================================================
+
+/// Tools provider enforcing [`RateLimits`] on tool calls.
+///
+/// Usually configured through
+/// [`ServerBuilder::rate_limit_tools`](crate::ServerBuilder::rate_limit_tools). Clones
+/// share their limiter state.
+#[derive(Clone, Debug)]
+pub struct RateLimited<T> {
+    inner: T,
+    limits: RateLimits,
+    limiter: Arc<Limiter>,
+}
+
+impl<T> RateLimited<T> {
+    /// Wrap a tools provider with limits.
+    pub fn new(inner: T, limits: RateLimits) -> Self {
+        Self {
+            inner,
+            limits,
+            limiter: Arc::default(),
+        }
+    }
+
+    /// Get a reference to the wrapped provider.
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    fn quotas(&self, tool: &str, session: Option<SessionId>) -> Vec<(Scope, Quota)> {
+        let mut quotas = Vec::new();
+        if let Some(quota) = self.limits.tools.get(tool) {
+            quotas.push((Scope::Tool(tool.to_owned()), *quota));
+        }
+        if let Some(quota) = self.limits.session {
+            quotas.push((Scope::Session(session), quota));
+        }
+        if let Some(quota) = self.limits.session_tools.get(tool) {
+            quotas.push((Scope::SessionTool(session, tool.to_owned()), *quota));
+        }
+        quotas
+    }
+}
+
+impl<T: ToolsProvider> ToolsProvider for RateLimited<T> {
+    async fn list_tools(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        self.inner.list_tools(request, context).await
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let tool = AliasChain::canonical_name(&context, &request.name);
+        let quotas = self.quotas(tool, SessionId::from_context(&context));
+        let _permit = self
+            .limiter
+            .acquire(&quotas, Instant::now())
+            .map_err(|mut error| {
+                error.message = format!("{} for tool '{tool}'", error.message).into();
+                error
+            })?;
+        self.inner.call_tool(request, context).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool_scope(quota: Quota) -> Vec<(Scope, Quota)> {
+        vec![(Scope::Tool("render".into()), quota)]
+    }
+
+    #[test]
+    fn test_token_bucket_refills() {
+        let limiter = Arc::new(Limiter::default());
+        let quotas = tool_scope(Quota::new().rate(Rate::per_second(2)));
+        let start = Instant::now();
+
+        drop(limiter.acquire(&quotas, start).unwrap());
+        drop(limiter.acquire(&quotas, start).unwrap());
+        let error = limiter.acquire(&quotas, start).err().unwrap();
+        assert_eq!(error.code, RATE_LIMITED);
+        let retry_after = error.data.unwrap()["retryAfterMs"].as_u64().unwrap();
+        assert!(retry_after > 0 && retry_after <= 501);
+
+        assert!(
+            limiter
+                .acquire(&quotas, start + Duration::from_millis(500))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one request")]
+    fn test_rate_rejects_zero_requests() {
+        let _ = Rate::per_second(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "rate period")]
+    fn test_rate_rejects_zero_period() {
+        let _ = Rate::new(10, Duration::ZERO);
+    }
+
+    #[test]
+    fn test_concurrency_cap() {
+        let limiter = Arc::new(Limiter::default());
+        let quotas = tool_scope(Quota::new().max_concurrency(1));
+        let now = Instant::now();
+
+        let permit = limiter.acquire(&quotas, now).unwrap();
+        assert!(limiter.acquire(&quotas, now).is_err());
+        drop(permit);
+        assert!(limiter.acquire(&quotas, now).is_ok());
+    }
+
+    #[test]
+    fn test_concurrency_rejection_hints_retry() {
+        let limiter = Arc::new(Limiter::default());
+        let quotas = tool_scope(Quota::new().max_concurrency(1));
+        let retry_after = |error: ErrorData| error.data.unwrap()["retryAfterMs"].as_u64().unwrap();
+
+        let permit = limiter.acquire(&quotas, Instant::now()).unwrap();
+        let error = limiter.acquire(&quotas, Instant::now()).err().unwrap();
+        assert_eq!(error.code, RATE_LIMITED);
+        assert_eq!(
+            retry_after(error),
+            DEFAULT_CONCURRENCY_RETRY.as_millis() as u64 + 1
+        );
+        drop(permit);
+
+        // Once calls complete, the hint follows their duration.
+        let started = Instant::now()
+            .checked_sub(Duration::from_millis(800))
+            .unwrap();
+        drop(limiter.acquire(&quotas, started).unwrap());
+        let _permit = limiter.acquire(&quotas, Instant::now()).unwrap();
+        let error = limiter.acquire(&quotas, Instant::now()).err().unwrap();
+        assert!(retry_after(error) >= 200);
+    }
+
+    #[test]
+    fn test_rejection_consumes_nothing() {
+        let limiter = Arc::new(Limiter::default());
+        let now = Instant::now();
+        let quotas = vec![
+            (Scope::Session(None), Quota::new().rate(Rate::per_second(1))),
+            (
+                Scope::Tool("render".into()),
+                Quota::new().max_concurrency(0),
+            ),
+        ];
+
+        assert!(limiter.acquire(&quotas, now).is_err());
+        let session_only = &quotas[..1];
+        assert!(limiter.acquire(session_only, now).is_ok());
+    }
+}
@@ -0,0 +1,153 @@
+//! Description of the requests a composed server dispatches to providers.
+
+use std::fmt;
+
+/// An MCP capability group, matching the provider traits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Capability {
+    /// Handled by a [`ToolsProvider`](crate::ToolsProvider).
+    Tools,
+    /// Handled by a [`PromptsProvider`](crate::PromptsProvider).
+    Prompts,
+    /// Handled by a [`ResourcesProvider`](crate::ResourcesProvider).
+    Resources,
+    /// Handled by a [`CompletionProvider`](crate::CompletionProvider).
+    Completion,
+    /// Handled by a [`LoggingProvider`](crate::LoggingProvider).
+    Logging,
+}
+
+impl Capability {
+    /// Get the capability name as used in MCP capability declarations.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Capability::Tools => "tools",
+            Capability::Prompts => "prompts",
+            Capability::Resources => "resources",
+            Capability::Completion => "completions",
+            Capability::Logging => "logging",
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A request dispatched by a composed server, with the tool, prompt or resource it targets.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RequestKind {
+    /// `tools/list`
+    ListTools,
+    /// `tools/call`
+    CallTool {
+        /// Name of the called tool.
+        name: String,
+    },
+    /// `prompts/list`
+    ListPrompts,
+    /// `prompts/get`
+    GetPrompt {
+        /// Name of the requested prompt.
+        name: String,
+    },
+    /// `resources/list`
+    ListResources,
+    /// `resources/templates/list`
+    ListResourceTemplates,
+    /// `resources/read`
+    ReadResource {
+        /// URI of the read resource.
+        uri: String,
+    },
+    /// `resources/subscribe`
+    Subscribe {
+        /// URI of the resource to subscribe to.
+        uri: String,
+    },
+    /// `resources/unsubscribe`
+    Unsubscribe {
+        /// URI of the resource to unsubscribe from.
+        uri: String,
+    },
+    /// `completion/complete`
+    Complete,
+    /// `logging/setLevel`
+    SetLevel,
+}
+
+impl RequestKind {
+    /// Get the MCP method name of the request.
+    pub fn method(&self) -> &'static str {
+        match self {
+            RequestKind::ListTools => "tools/list",
+            RequestKind::CallTool { .. } => "tools/call",
+            RequestKind::ListPrompts => "prompts/list",
+            RequestKind::GetPrompt { .. } => "prompts/get",
+            RequestKind::ListResources => "resources/list",
+            RequestKind::ListResourceTemplates => "resources/templates/list",
+            RequestKind::ReadResource { .. } => "resources/read",
+            RequestKind::Subscribe { .. } => "resources/subscribe",
+            RequestKind::Unsubscribe { .. } => "resources/unsubscribe",
+            RequestKind::Complete => "completion/complete",
+            RequestKind::SetLevel => "logging/setLevel",
+        }
+    }
+
+    /// Get the capability the request belongs to.
+    pub fn capability(&self) -> Capability {
+        match self {
+            RequestKind::ListTools | RequestKind::CallTool { .. } => Capability::Tools,
+            RequestKind::ListPrompts | RequestKind::GetPrompt { .. } => Capability::Prompts,
+            RequestKind::ListResources
+            | RequestKind::ListResourceTemplates
+            | RequestKind::ReadResource { .. }
+            | RequestKind::Subscribe { .. }
+            | RequestKind::Unsubscribe { .. } => Capability::Resources,
+            RequestKind::Complete => Capability::Completion,
+            RequestKind::SetLevel => Capability::Logging,
+        }
+    }
+
+    /// Get the name of the called tool, if this is a tool call.
+    pub fn tool_name(&self) -> Option<&str> {
+        match self {
+            RequestKind::CallTool { name } => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Get the name of the requested prompt, if this is a prompt request.
+    pub fn prompt_name(&self) -> Option<&str> {
+        match self {
+            RequestKind::GetPrompt { name } => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Get the resource URI targeted by the request, if any.
+    pub fn resource_uri(&self) -> Option<&str> {
+        match self {
+            RequestKind::ReadResource { uri }
+            | RequestKind::Subscribe { uri }
+            | RequestKind::Unsubscribe { uri } => Some(uri),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RequestKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.method())?;
+        match self
+            .tool_name()
+            .or(self.prompt_name())
+            .or(self.resource_uri())
+        {
+            Some(target) => write!(f, " '{target}'"),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,658 @@
+//! Retry and fallback combinators for flaky providers.
+//!
+//! [`Retry`] re-runs failed requests of a tools or resources provider according to a
+//! [`RetryPolicy`], waiting between attempts with a configurable [`Backoff`].
+//! [`Fallback`] sends requests to a secondary provider when the primary one fails with
+//! selected error codes.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{BuildHasher, RandomState};
+use std::sync::Arc;
+use std::time::Duration;
+
+use rmcp::{
+    model::{
+        CallToolRequestParams, CallToolResult, ErrorCode, ErrorData, ListResourceTemplatesResult,
+        ListResourcesResult, ListToolsResult, PaginatedRequestParams, ReadResourceRequestParams,
+        ReadResourceResult, SubscribeRequestParams, UnsubscribeRequestParams,
+    },
+    service::{RequestContext, RoleServer},
+};
+
+use crate::providers::{ResourcesProvider, ToolsProvider};
+
+/// Delays between retry attempts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    multiplier: f64,
+    jitter: bool,
+}
+
+impl Backoff {
+    /// Wait the same delay before every retry.
+    pub fn fixed(delay: Duration) -> Self {
+        Self {
+            initial: delay,
+            max: delay,
+            multiplier: 1.0,
+            jitter: false,
+        }
+    }
+
+    /// Double the delay after every retry, starting at `initial` and capped at `max`.
+    pub fn exponential(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            multiplier: 2.0,
+            jitter: false,
+        }
+    }
+
+    /// Set the factor applied to the delay after every retry.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `multiplier` is not a finite number of at least 1.
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        assert!(
+            multiplier.is_finite() && multiplier >= 1.0,
+            "backoff multiplier must be finite and at least 1"
+        );
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Randomize each delay between half and all of its value.
+    pub fn jitter(mut self) -> Self {
+        self.jitter = true;
+        self
+    }
+
+    /// Get the delay before the given retry, starting at 0 for the first retry.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = self.multiplier.powi(retry.min(1024) as i32);
+        // A zero delay stays zero even once the factor overflows to infinity.
+        let scaled = match self.initial.is_zero() {
+            true => 0.0,
+            false => self.initial.as_secs_f64() * factor,
+        };
+        let delay =
+            Duration::try_from_secs_f64(scaled).map_or(self.max, |delay| delay.min(self.max));
+        if self.jitter {
+            let random = RandomState::new().hash_one(retry) as f64 / u64::MAX as f64;
+            delay.mul_f64(0.5 + random / 2.0)
+        } else {
+            delay
+        }
+    }
+}
+
+impl Default for Backoff {
+    /// Exponential backoff from 100 milliseconds up to 5 seconds.
+    fn default() -> Self {
+        Self::exponential(Duration::from_millis(100), Duration::from_secs(5))
+    }
+}
+
+type Classifier = Arc<dyn Fn(&ErrorData) -> bool + Send + Sync>;
+
+/// When and how often a [`Retry`] provider re-runs failed requests.
+///
+/// By default, requests are attempted up to 3 times with the default [`Backoff`], and
+/// only internal errors are retried.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use rmcp::model::ErrorCode;
+/// use rmcp_server_builder::{Backoff, RetryPolicy};
+///
+/// let policy = RetryPolicy::new()
+///     .max_attempts(5)
+///     .backoff(Backoff::exponential(Duration::from_millis(50), Duration::from_secs(2)).jitter())
+///     .retry_if(|error| error.code == ErrorCode::INTERNAL_ERROR || error.code.0 == -32001);
+/// ```
+#[derive(Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    backoff: Backoff,
+    classifier: Classifier,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff: Backoff::default(),
+            classifier: Arc::new(|error| error.code == ErrorCode::INTERNAL_ERROR),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Create the default policy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of attempts, including the first one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_attempts` is zero.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        assert!(max_attempts > 0, "at least one attempt is required");
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Set the delays between attempts.
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Retry errors for which `classifier` returns `true`.
+    pub fn retry_if(
+        mut self,
+        classifier: impl Fn(&ErrorData) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.classifier = Arc::new(classifier);
+        self
+    }
+
+    /// Retry errors with one of the given codes.
+    pub fn retry_codes(self, codes: impl IntoIterator<Item = ErrorCode>) -> Self {
+        let codes: HashSet<i32> = codes.into_iter().map(|code| code.0).collect();
+        self.retry_if(move |error| codes.contains(&error.code.0))
+    }
+
+    /// Check whether an error should be retried.
+    pub fn is_retryable(&self, error: &ErrorData) -> bool {
+        (self.classifier)(error)
+    }
+
+    /// Run `attempt` until it succeeds, fails with a non-retryable error, runs out of
+    /// attempts or the request is cancelled.
+    async fn run<Res, Fut>(
+        &self,
+        context: &RequestContext<RoleServer>,
+        mut attempt: impl FnMut(RequestContext<RoleServer>) -> Fut,
+    ) -> Result<Res, ErrorData>
+    where
+        Fut: Future<Output = Result<Res, ErrorData>>,
+    {
+        let mut retry = 0;
+        loop {
+            let error = match attempt(context.clone()).await {
+                Ok(result) => return Ok(result),
+                Err(error) => error,
+            };
+            if retry + 1 >= self.max_attempts || !self.is_retryable(&error) {
+                return Err(error);
+            }
+            let delay = self.backoff.delay(retry);
+            if context
+                .ct
+                .run_until_cancelled(tokio::time::sleep(delay))
+                .await
+                .is_none()
+            {
+                return Err(error);
+            }
+            retry += 1;
+        }
+    }
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("backoff", &self.backoff)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Provider retrying failed requests according to a [`RetryPolicy`].
+///
+/// Every request is retried, including `call_tool`: only wrap tools that are safe to
+/// call again after a failure. Usually configured through
+/// [`ServerBuilder::retry_tools`](crate::ServerBuilder::retry_tools) or
+/// [`ServerBuilder::retry_resources`](crate::ServerBuilder::retry_resources).
+#[derive(Clone, Debug)]
+pub struct Retry<P> {
+    inner: P,
+    policy: RetryPolicy,
+}
+
+impl<P> Retry<P> {
+    /// Wrap a provider with a retry policy.
+    pub fn new(inner: P, policy: RetryPolicy) -> Self {
+        Self { inner, policy }
+    }
+
+    /// Get a reference to the wrapped provider.
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+}
+
+impl<T: ToolsProvider> ToolsProvider for Retry<T> {
+    async fn list_tools(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        self.policy
+            .run(&context, |context| {
+                self.inner.list_tools(request.clone(), context)
+            })
+            .await
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.policy
+            .run(&context, |context| {
+                self.inner.call_tool(request.clone(), context)
+            })
+            .await
+    }
+}
+
+impl<R: ResourcesProvider> ResourcesProvider for Retry<R> {
+    async fn list_resources(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, ErrorData> {
+        self.policy
+            .run(&context, |context| {
+                self.inner.list_resources(request.clone(), context)
+            })
+            .await
+    }
+
+    async fn list_resource_templates(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, ErrorData> {
+        self.policy
+            .run(&context, |context| {
+                self.inner.list_resource_templates(request.clone(), context)
+            })
+            .await
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, ErrorData> {
+        self.policy
+            .run(&context, |context| {
+                self.inner.read_resource(request.clone(), context)
+            })
+            .await
+    }
+
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        self.policy
+            .run(&context, |context| {
+                self.inner.subscribe(request.clone(), context)
+            })
+            .await
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        self.policy
+            .run(&context, |context| {
+                self.inner.unsubscribe(request.clone(), context)
+            })
+            .await
+    }
+}
+
+// =============================================================================
+// Fallback
+// =============================================================================
+
+/// Provider sending requests to a secondary provider when the primary one fails.
+///
+/// By default the secondary provider is used when the primary one fails with an
+/// internal error; [`on_code`](Self::on_code) selects other error codes.
+///
+/// # Example
+///
+/// ```no_run
+/// use rmcp::model::{ErrorCode, Implementation};
+/// use rmcp_server_builder::{Fallback, ServerBuilder, ToolsProvider};
+///
+/// # fn compose(info: Implementation, live_backend: impl ToolsProvider, cached_backend: impl ToolsProvider) {
+/// let server = ServerBuilder::new()
+///     .info(info)
+///     .tools(Fallback::new(live_backend, cached_backend).on_code(ErrorCode(-32001)))
+///     .build();
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Fallback<A, B> {
+    primary: A,
+    secondary: B,
+    codes: HashSet<i32>,
+}
+
+impl<A, B> Fallback<A, B> {
+    /// Try `primary` first and fall back to `secondary` on internal errors.
+    pub fn new(primary: A, secondary: B) -> Self {
+        Self {
+            primary,
+            secondary,
+            codes: HashSet::from([ErrorCode::INTERNAL_ERROR.0]),
+        }
+    }
+
+    /// Also fall back when the primary provider fails with `code`.
+    pub fn on_code(mut self, code: ErrorCode) -> Self {
+        self.codes.insert(code.0);
+        self
+    }
+
+    /// Get a reference to the primary provider.
+    pub fn primary(&self) -> &A {
+        &self.primary
+    }
+
+    /// Get a reference to the secondary provider.
+    pub fn secondary(&self) -> &B {
+        &self.secondary
+    }
+
+    async fn run<Res, Fut>(
+        &self,
+        primary: impl Future<Output = Result<Res, ErrorData>>,
+        secondary: impl FnOnce() -> Fut,
+    ) -> Result<Res, ErrorData>
+    where
+        Fut: Future<Output = Result<Res, ErrorData>>,
+    {
+        match primary.await {
+            Err(error) if self.codes.contains(&error.code.0) => secondary().await,
+            result => result,
+        }
+    }
+}
+
+impl<A: ToolsProvider, B: ToolsProvider> ToolsProvider for Fallback<A, B> {
+    async fn list_tools(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        self.run(
+            self.primary.list_tools(request.clone(), context.clone()),
+            || self.secondary.list_tools(request, context),
+        )
+        .await
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.run(
+            self.primary.call_tool(request.clone(), context.clone()),
+            || self.secondary.call_tool(request, context),
+        )
+        .await
+    }
+}
+
+impl<A: ResourcesProvider, B: ResourcesProvider> ResourcesProvider for Fallback<A, B> {
+    async fn list_resources(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, ErrorData> {
+        self.run(
+            self.primary
+                .list_resources(request.clone(), context.clone()),
+            || self.secondary.list_resources(request, context),
+        )
+        .await
+    }
+
+    async fn list_resource_templates(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, ErrorData> {
+        self.run(
+            self.primary
+                .list_resource_templates(request.clone(), context.clone()),
+            || self.secondary.list_resource_templates(request, context),
+        )
+        .await
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, ErrorData> {
+        self.run(
+            self.primary.read_resource(request.clone(), context.clone()),
+            || self.secondary.read_resource(request, context),
+        )
+        .await
+    }
+
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        self.run(
+            self.primary.subscribe(request.clone(), context.clone()),
+            || self.secondary.subscribe(request, context),
+        )
+        .await
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        self.run(
+            self.primary.unsubscribe(request.clone(), context.clone()),
+            || self.secondary.unsubscribe(request, context),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_delays() {
+        let backoff = Backoff::exponential(Duration::from_millis(100), Duration::from_secs(1));
+        assert_eq!(backoff.delay(0), Duration::from_millis(100));
+        assert_eq!(backoff.delay(2), Duration::from_millis(400));
+        assert_eq!(backoff.delay(10), Duration::from_secs(1));
+        assert_eq!(backoff.delay(u32::MAX), Duration::from_secs(1));
+
+        let jittered = backoff.jitter().delay(1);
+        assert!(jittered >= Duration::from_millis(100) && jittered <= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn test_backoff_extremes() {
+        let zero = Backoff::exponential(Duration::ZERO, Duration::from_secs(1));
+        assert_eq!(zero.delay(u32::MAX), Duration::ZERO);
+        let unbounded = Backoff::exponential(Duration::from_secs(1), Duration::MAX);
+        assert_eq!(unbounded.delay(u32::MAX), Duration::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "backoff multiplier")]
+    fn test_backoff_rejects_shrinking_multiplier() {
+        let _ = Backoff::default().multiplier(-2.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "backoff multiplier")]
+    fn test_backoff_rejects_nan_multiplier() {
+        let _ = Backoff::default().multiplier(f64::NAN);
+    }
+
+    #[test]
+    fn test_retry_codes() {
+        let policy = RetryPolicy::new().retry_codes([ErrorCode(-32001)]);
+        assert!(policy.is_retryable(&ErrorData::new(ErrorCode(-32001), "busy", None)));
+        assert!(!policy.is_retryable(&ErrorData::internal_error("boom", None)));
+    }
+
+    mod session {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        use rmcp::model::{Content, Implementation, ListToolsResult};
+        use rmcp::service::{RoleClient, RunningService, ServiceError};
+
+        use super::*;
+        use crate::{ServerBuilder, test_support};
+
+        /// Tools provider failing its first `failures` calls with `code`.
+        #[derive(Clone)]
+        struct Flaky {
+            label: &'static str,
+            code: ErrorCode,
+            failures: u32,
+            calls: Arc<AtomicU32>,
+        }
+
+        impl Flaky {
+            fn new(label: &'static str, code: ErrorCode, failures: u32) -> Self {
+                Self {
+                    label,
+                    code,
+                    failures,
+                    calls: Arc::default(),
+                }
+            }
+
+            fn calls(&self) -> u32 {
+                self.calls.load(Ordering::Relaxed)
+            }
+        }
+
+        impl ToolsProvider for Flaky {
+            async fn list_tools(
+                &self,
+                _request: Option<PaginatedRequestParams>,
+                _context: RequestContext<RoleServer>,
+            ) -> Result<ListToolsResult, ErrorData> {
+                Ok(ListToolsResult::default())
+            }
+
+            async fn call_tool(
+                &self,
+                _request: CallToolRequestParams,
+                _context: RequestContext<RoleServer>,
+            ) -> Result<CallToolResult, ErrorData> {
+                match self.calls.fetch_add(1, Ordering::Relaxed) < self.failures {
+                    true => Err(ErrorData::new(self.code, self.label, None)),
+                    false => Ok(CallToolResult::success(vec![Content::text(self.label)])),
+                }
+            }
+        }
+
+        async fn connect(tools: impl ToolsProvider) -> RunningService<RoleClient, ()> {
+            let server = ServerBuilder::new()
+                .info(Implementation::default())
+                .tools(tools)
+                .build();
+            test_support::connect(server, ()).await
+        }
+
+        async fn call(client: &RunningService<RoleClient, ()>) -> Result<String, ErrorData> {
+            match client.call_tool(test_support::call("flaky")).await {
+                Ok(result) => Ok(result.content[0].as_text().unwrap().text.clone()),
+                Err(ServiceError::McpError(error)) => Err(error),
+                Err(error) => panic!("unexpected error: {error}"),
+            }
+        }
+
+        fn policy() -> RetryPolicy {
+            RetryPolicy::new()
+                .max_attempts(3)
+                .backoff(Backoff::fixed(Duration::ZERO))
+        }
+
+        #[tokio::test]
+        async fn test_retry_until_success() {
+            let flaky = Flaky::new("flaky", ErrorCode::INTERNAL_ERROR, 2);
+            let client = connect(Retry::new(flaky.clone(), policy())).await;
+
+            assert_eq!(call(&client).await.unwrap(), "flaky");
+            assert_eq!(flaky.calls(), 3);
+        }
+
+        #[tokio::test]
+        async fn test_retry_stops_at_max_attempts() {
+            let flaky = Flaky::new("flaky", ErrorCode::INTERNAL_ERROR, 5);
+            let client = connect(Retry::new(flaky.clone(), policy())).await;
+
+            let error = call(&client).await.unwrap_err();
+            assert_eq!(error.code, ErrorCode::INTERNAL_ERROR);
+            assert_eq!(flaky.calls(), 3);
+        }
+
+        #[tokio::test]
+        async fn test_retry_stops_on_non_retryable_error() {
+            let flaky = Flaky::new("flaky", ErrorCode::INVALID_PARAMS, 5);
+            let client = connect(Retry::new(flaky.clone(), policy())).await;
+
+            let error = call(&client).await.unwrap_err();
+            assert_eq!(error.code, ErrorCode::INVALID_PARAMS);
+            assert_eq!(flaky.calls(), 1);
+        }
+
+        #[tokio::test]
+        async fn test_fallback_serves_from_secondary() {
+            let primary = Flaky::new("primary", ErrorCode(-32001), u32::MAX);
+            let secondary = Flaky::new("secondary", ErrorCode::INTERNAL_ERROR, 0);
+            let fallback = Fallback::new(primary.clone(), secondary.clone());
+
+            // Only selected codes fall back.
+            let client = connect(fallback.clone()).await;
+            assert_eq!(call(&client).await.unwrap_err().code, ErrorCode(-32001));
+            assert_eq!(secondary.calls(), 0);
+
+            let client = connect(fallback.on_code(ErrorCode(-32001))).await;
+            assert_eq!(call(&client).await.unwrap(), "secondary");
+            assert_eq!((primary.calls(), secondary.calls()), (2, 1));
+        }
+    }
+}
@@ -0,0 +1,348 @@
+//! Tool metadata rewriting.
+//!
+//! [`RewrittenTools`] wraps a tools provider and overrides the metadata of selected
+//! tools in `list_tools`, so descriptions, titles, annotations and input schemas of
+//! third-party tools can be improved without forking the upstream provider.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use rmcp::{
+    model::{
+        CallToolRequestParams, CallToolResult, ErrorData, JsonObject, ListToolsResult,
+        PaginatedRequestParams, Tool, ToolAnnotations,
+    },
+    service::{RequestContext, RoleServer},
+};
+use serde_json::Value;
+
+use crate::providers::ToolsProvider;
+
+/// Metadata overrides for a single tool.
+///
+/// Only the fields that are set replace the upstream metadata.
+#[derive(Clone, Debug, Default)]
+pub struct ToolOverride {
+    title: Option<String>,
+    description: Option<Cow<'static, str>>,
+    annotations: Option<ToolAnnotations>,
+    schema_patch: Option<JsonObject>,
+    property_descriptions: Vec<(String, String)>,
+    /// Properties already reported as missing from the input schema.
+    unknown_properties: Arc<Mutex<HashSet<String>>>,
+}
+
+impl ToolOverride {
+    /// Create an override that leaves the tool unchanged.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the tool's title.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Replace the tool's description.
+    pub fn description(mut self, description: impl Into<Cow<'static, str>>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Override annotation hints.
+    ///
+    /// Hints set in `annotations` replace the upstream ones; unset hints are kept.
+    pub fn annotations(mut self, annotations: ToolAnnotations) -> Self {
+        self.annotations = Some(annotations);
+        self
+    }
+
+    /// Merge a JSON merge patch (RFC 7396) into the tool's input schema.
+    ///
+    /// Objects are merged recursively, `null` removes a key and any other value
+    /// replaces the upstream one.
+    pub fn schema_patch(mut self, patch: JsonObject) -> Self {
+        self.schema_patch = Some(patch);
+        self
+    }
+
+    /// Replace the description of a single input schema property.
+    ///
+    /// Only properties present in the input schema, after any
+    /// [`schema_patch`](Self::schema_patch), are described; unknown properties are
+    /// skipped rather than added to the schema.
+    pub fn property_description(
+        mut self,
+        property: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Self {
+        self.property_descriptions
+            .push((property.into(), description.into()));
+        self
+    }
+
+    /// Apply this override to a tool definition.
+    pub fn apply(&self, tool: &mut Tool) {
+        if let Some(title) = &self.title {
+            tool.title = Some(title.clone());
+        }
+        if let Some(description) = &self.description {
+            tool.description = Some(description.clone());
+        }
+        if let Some(overrides) = &self.annotations {
+            let annotations = tool
+                .annotations
+                .get_or_insert_with(ToolAnnotations::default);
+            merge_annotations(annotations, overrides);
+        }
+        if let Some(patch) = &self.schema_patch {
+            merge_patch(Arc::make_mut(&mut tool.input_schema), patch);
+        }
+        for (property, description) in &self.property_descriptions {
+            let field = Arc::make_mut(&mut tool.input_schema)
+                .get_mut("properties")
+                .and_then(Value::as_object_mut)
+                .and_then(|properties| properties.get_mut(property))
+                .and_then(Value::as_object_mut);
+            match field {
+                Some(field) => {
+                    field.insert("description".into(), Value::String(description.clone()));
+                }
+                None => self.report_unknown(tool, property),
+            }
+        }
+    }
+
+    fn report_unknown(&self, tool: &Tool, property: &str) {
+        let first = self
+            .unknown_properties
+            .lock()
+            .unwrap()
+            .insert(property.to_owned());
+        if first {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                tool = %tool.name,
+                %property,
+                "described property is not in the input schema and is ignored"
+            );
+        }
+        #[cfg(not(feature = "tracing"))]
+        let _ = tool;
+    }
+}
+
+fn merge_annotations(target: &mut ToolAnnotations, overrides: &ToolAnnotations) {
+    if overrides.title.is_some() {
+        target.title = overrides.title.clone();
+    }
+    if overrides.read_only_hint.is_some() {
+        target.read_only_hint = overrides.read_only_hint;
+    }
+    if overrides.destructive_hint.is_some() {
+        target.destructive_hint = overrides.destructive_hint;
+    }
+    if overrides.idempotent_hint.is_some() {
+        target.idempotent_hint = overrides.idempotent_hint;
+    }
+    if overrides.open_world_hint.is_some() {
+        target.open_world_hint = overrides.open_world_hint;
+    }
+}
+
+/// Apply an RFC 7396 JSON merge patch to an object.
+fn merge_patch(target: &mut JsonObject, patch: &JsonObject) {
+    for (key, value) in patch {
+        match value {
+            Value::Null => {
+                target.remove(key);
+            }
+            Value::Object(patch) => {
+                let entry = target
+                    .entry(key.clone())
+                    .or_insert_with(|| Value::Object(JsonObject::new()));
+                if !entry.is_object() {
+                    *entry = Value::Object(JsonObject::new());
+                }
+                if let Value::Object(target) = entry {
+                    merge_patch(target, patch);
+                }
+            }
+            value => {
+                target.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// Metadata overrides for a set of tools, keyed by tool name.
+///
+/// # Example
+///
+/// ```
+/// use rmcp::model::ToolAnnotations;
+/// use rmcp_server_builder::{ToolOverride, ToolOverrides};
+///
+/// let overrides = ToolOverrides::new()
+///     .tool(
+///         "get_issue",
+///         ToolOverride::new()
+///             .description("Fetch a single issue by its number.")
+///             .annotations(ToolAnnotations::new().read_only(true))
+///             .property_description("number", "Issue number, without the leading #."),
+///     );
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ToolOverrides {
+    overrides: HashMap<String, ToolOverride>,
+}
+
+impl ToolOverrides {
+    /// Create an empty set of overrides.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the override for a tool, replacing any previous one.
+    pub fn tool(mut self, name: impl Into<String>, tool_override: ToolOverride) -> Self {
+        self.overrides.insert(name.into(), tool_override);
+        self
+    }
+
+    /// Apply the matching override, if any, to a tool definition.
+    pub fn apply(&self, tool: &mut Tool) {
+        if let Some(tool_override) = self.overrides.get(tool.name.as_ref()) {
+            tool_override.apply(tool);
+        }
+    }
+}
+
+/// Tools provider rewriting tool metadata with [`ToolOverrides`].
+///
+/// Usually configured through [`ServerBuilder::rewrite_tools`](crate::ServerBuilder::rewrite_tools).
+#[derive(Clone, Debug)]
+pub struct RewrittenTools<T> {
+    inner: T,
+    overrides: ToolOverrides,
+}
+
+impl<T> RewrittenTools<T> {
+    /// Wrap a tools provider with metadata overrides.
+    pub fn new(inner: T, overrides: ToolOverrides) -> Self {
+        Self { inner, overrides }
+    }
+
+    /// Get a reference to the wrapped provider.
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: ToolsProvider> ToolsProvider for RewrittenTools<T> {
+    async fn list_tools(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        let mut result = self.inner.list_tools(request, context).await?;
+        for tool in &mut result.tools {
+            self.overrides.apply(tool);
+        }
+        Ok(result)
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.inner.call_tool(request, context).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn object(value: Value) -> JsonObject {
+        match value {
+            Value::Object(object) => object,
+            _ => panic!("expected an object"),
+        }
+    }
+
+    #[test]
+    fn test_merge_patch() {
+        let mut target = object(json!({
+            "type": "object",
+            "properties": { "a": { "type": "string" }, "b": { "type": "number" } }
+        }));
+        merge_patch(
+            &mut target,
+            &object(json!({
+                "properties": { "a": { "description": "first" }, "b": null },
+                "required": ["a"]
+            })),
+        );
+        assert_eq!(
+            Value::Object(target),
+            json!({
+                "type": "object",
+                "properties": { "a": { "type": "string", "description": "first" } },
+                "required": ["a"]
+            })
+        );
+    }
+
+    #[test]
+    fn test_override_keeps_unset_fields() {
+        let schema = object(json!({ "properties": { "query": { "type": "string" } } }));
+        let mut tool = Tool::new("search", "old", Arc::new(schema))
+            .annotate(ToolAnnotations::new().open_world(true));
+        ToolOverrides::new()
+            .tool(
+                "search",
+                ToolOverride::new()
+                    .description("new")
+                    .annotations(ToolAnnotations::new().read_only(true))
+                    .property_description("query", "Search terms"),
+            )
+            .apply(&mut tool);
+
+        assert_eq!(tool.description.as_deref(), Some("new"));
+        let annotations = tool.annotations.unwrap();
+        assert_eq!(annotations.read_only_hint, Some(true));
+        assert_eq!(annotations.open_world_hint, Some(true));
+        assert_eq!(
+            Value::Object((*tool.input_schema).clone()),
+            json!({
+                "properties": { "query": { "type": "string", "description": "Search terms" } }
+            })
+        );
+    }
+
+    #[test]
+    fn test_skips_unknown_property_description() {
+        let schema = json!({ "properties": { "query": { "type": "string" } } });
+        let mut tool = Tool::new("search", "", Arc::new(object(schema.clone())));
+        ToolOverride::new()
+            .property_description("qeury", "Search terms")
+            .apply(&mut tool);
+        assert_eq!(Value::Object((*tool.input_schema).clone()), schema);
+
+        let mut tool = Tool::new("search", "", Arc::new(object(schema)));
+        ToolOverride::new()
+            .schema_patch(object(
+                json!({ "properties": { "limit": { "type": "integer" } } }),
+            ))
+            .property_description("limit", "Maximum number of results")
+            .apply(&mut tool);
+        assert_eq!(
+            tool.input_schema["properties"]["limit"]["description"],
+            "Maximum number of results"
+        );
+    }
+}
@@ -0,0 +1,390 @@
+//! Requests for completions from the client's model.
+//!
+//! [`Sampling`] builds a `sampling/createMessage` request from any provider method
+//! holding a [`RequestContext`], so composed tools can ask the client's model for help.
+//! It checks that the client supports sampling, bounds the wait with a timeout and
+//! returns a [`SamplingReply`] with the text, or a value parsed from it.
+
+use std::time::Duration;
+
+use rmcp::{
+    ServiceError,
+    model::{
+        ClientResult, ContextInclusion, CreateMessageRequest, CreateMessageRequestParams,
+        CreateMessageResult, ErrorData, ModelHint, ModelPreferences, SamplingMessage,
+        SamplingMessageContent, ServerRequest,
+    },
+    service::{PeerRequestOptions, RequestContext, RoleServer},
+};
+use serde::de::DeserializeOwned;
+
+use crate::session::ConnectedClient;
+
+/// Default time to wait for the client's model to answer a sampling request.
+pub const DEFAULT_SAMPLING_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Request for a completion from the client's model.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use rmcp::model::ErrorData;
+/// use rmcp::service::{RequestContext, RoleServer};
+/// use rmcp_server_builder::Sampling;
+///
+/// # async fn summarize(document: String, context: RequestContext<RoleServer>) -> Result<String, ErrorData> {
+/// // In a tools provider's `call_tool`:
+/// let summary = Sampling::new(500)
+///     .system_prompt("Summarize the document in one paragraph.")
+///     .user(document)
+///     .timeout(Duration::from_secs(30))
+///     .send(&context)
+///     .await?
+///     .text();
+/// # Ok(summary)
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Sampling {
+    params: CreateMessageRequestParams,
+    timeout: Duration,
+}
+
+impl Sampling {
+    /// Create a request generating at most `max_tokens` tokens.
+    pub fn new(max_tokens: u32) -> Self {
+        Self {
+            params: CreateMessageRequestParams {
+                meta: None,
+                task: None,
+                messages: Vec::new(),
+                model_preferences: None,
+                system_prompt: None,
+                include_context: None,
+                temperature: None,
+                max_tokens,
+                stop_sequences: None,
+                metadata: None,
+                tools: None,
+                tool_choice: None,
+            },
+            timeout: DEFAULT_SAMPLING_TIMEOUT,
+        }
+    }
+
+    /// Append a message to the conversation.
+    pub fn message(mut self, message: SamplingMessage) -> Self {
+        self.params.messages.push(message);
+        self
+    }
+
+    /// Append a user text message to the conversation.
+    pub fn user(self, text: impl Into<String>) -> Self {
+        self.message(SamplingMessage::user_text(text))
+    }
+
+    /// Append an assistant text message to the conversation.
+    pub fn assistant(self, text: impl Into<String>) -> Self {
+        self.message(SamplingMessage::assistant_text(text))
+    }
+
+    /// Set the system prompt.
+    pub fn system_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.params.system_prompt = Some(prompt.into());
+        self
+    }
+
+    /// Set the sampling temperature.
+    pub fn temperature(mut self, temperature: f32) -> Self {
+        self.params.temperature = Some(temperature);
+        self
+    }
+
+    /// Add a sequence stopping the generation.
+    pub fn stop_sequence(mut self, sequence: impl Into<String>) -> Self {
+        self.params
+            .stop_sequences
+            .get_or_insert_with(Vec::new)
+            .push(sequence.into());
+        self
+    }
+
+    /// Set the preferences guiding the client's choice of model.
+    pub fn model_preferences(mut self, preferences: ModelPreferences) -> Self {
+        self.params.model_preferences = Some(preferences);
+        self
+    }
+
+    /// Suggest a model name or family, in order of preference.
+    pub fn model_hint(mut self, name: impl Into<String>) -> Self {
+        let preferences = self
+            .params
+            .model_preferences
+            .get_or_insert(ModelPreferences {
+                hints: None,
+                cost_priority: None,
+                speed_priority: None,
+                intelligence_priority: None,
+            });
+        preferences
+            .hints
+            .get_or_insert_with(Vec::new)
+            .push(ModelHint {
+                name: Some(name.into()),
+            });
+        self
+    }
+
+    /// Ask the client to include context from MCP servers.
+    pub fn include_context(mut self, context: ContextInclusion) -> Self {
+        self.params.include_context = Some(context);
+        self
+    }
+
+    /// Set how long to wait for the completion.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Send the request to the client of the session of `context`.
+    ///
+    /// Fails with an invalid request error when the client does not support sampling,
+    /// and with an internal error when it does not answer before the timeout or the
+    /// request of `context` is cancelled first. Errors returned by the client are
+    /// passed through.
+    pub async fn send(
+        self,
+        context: &RequestContext<RoleServer>,
+    ) -> Result<SamplingReply, ErrorData> {
+        if !ConnectedClient::from_context(context).is_some_and(|client| client.supports_sampling())
+        {
+            return Err(ErrorData::invalid_request(
+                "the client does not support sampling",
+                None,
+            ));
+        }
+        self.params
+            .validate()
+            .map_err(|error| ErrorData::invalid_params(error, None))?;
+
+        let request = ServerRequest::CreateMessageRequest(CreateMessageRequest {
+            method: Default::default(),
+            params: self.params,
+            extensions: Default::default(),
+        });
+        match request_client(context, request, Some(self.timeout), "sampling").await? {
+            ClientResult::CreateMessageResult(result) => Ok(SamplingReply { result: *result }),
+            _ => Err(ErrorData::internal_error(
+                "unexpected response to sampling request",
+                None,
+            )),
+        }
+    }
+}
+
+/// Send `request` to the client of the session of `context` and wait for its answer.
+///
+/// Stops waiting after `timeout`, if any, or when the request of `context` is cancelled.
+pub(crate) async fn request_client(
+    context: &RequestContext<RoleServer>,
+    request: ServerRequest,
+    timeout: Option<Duration>,
+    name: &str,
+) -> Result<ClientResult, ErrorData> {
+    let options = PeerRequestOptions {
+        timeout,
+        meta: None,
+    };
+    let response = async {
+        context
+            .peer
+            .send_request_with_option(request, options)
+            .await?
+            .await_response()
+            .await
+    };
+    match context.ct.run_until_cancelled(response).await {
+        Some(Ok(result)) => Ok(result),
+        Some(Err(ServiceError::McpError(error))) => Err(error),
+        Some(Err(ServiceError::Timeout { timeout })) => Err(ErrorData::internal_error(
+            format!("{name} request timed out after {timeout:?}"),
+            None,
+        )),
+        Some(Err(error)) => Err(ErrorData::internal_error(
+            format!("{name} request failed: {error}"),
+            None,
+        )),
+        None => Err(ErrorData::internal_error(
+            format!("{name} request cancelled"),
+            None,
+        )),
+    }
+}
+
+/// Completion generated by the client's model.
+#[derive(Clone, Debug)]
+pub struct SamplingReply {
+    result: CreateMessageResult,
+}
+
+impl SamplingReply {
+    /// Get the name of the model that generated the completion.
+    pub fn model(&self) -> &str {
+        &self.result.model
+    }
+
+    /// Get the reason the generation stopped, such as `endTurn` or `maxTokens`.
+    pub fn stop_reason(&self) -> Option<&str> {
+        self.result.stop_reason.as_deref()
+    }
+
+    /// Get the text of the completion, joining its text contents.
+    pub fn text(&self) -> String {
+        self.result
+            .message
+            .content
+            .iter()
+            .filter_map(|content| match content {
+                SamplingMessageContent::Text(text) => Some(text.text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Parse the text of the completion as JSON.
+    ///
+    /// A Markdown code fence around the JSON is ignored. Fails with an internal error
+    /// when the text is not a valid `T`.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, ErrorData> {
+        let text = self.text();
+        serde_json::from_str(strip_code_fence(&text)).map_err(|error| {
+            ErrorData::internal_error(format!("invalid sampled JSON: {error}"), None)
+        })
+    }
+
+    /// Get the raw result of the sampling request.
+    pub fn into_result(self) -> CreateMessageResult {
+        self.result
+    }
+}
+
+/// Strip a Markdown code fence, with an optional language tag, around `text`.
+fn strip_code_fence(text: &str) -> &str {
+    let text = text.trim();
+    let Some(fenced) = text
+        .strip_prefix("```")
+        .and_then(|text| text.strip_suffix("```"))
+    else {
+        return text;
+    };
+    match fenced.split_once('\n') {
+        Some((_language, body)) => body.trim(),
+        None => fenced.trim(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_code_fence() {
+        assert_eq!(strip_code_fence(" [1, 2] "), "[1, 2]");
+        assert_eq!(strip_code_fence("```json\n[1, 2]\n```"), "[1, 2]");
+        assert_eq!(strip_code_fence("```\n[1, 2]\n```"), "[1, 2]");
+    }
+
+    mod client {
+        use rmcp::handler::client::ClientHandler;
+        use rmcp::model::{
+            CallToolRequestParams, CallToolResult, ClientCapabilities, ClientInfo, Content,
+            ErrorCode, Implementation, ListToolsResult, PaginatedRequestParams, Role,
+        };
+        use rmcp::service::RoleClient;
+
+        use super::*;
+        use crate::providers::ToolsProvider;
+        use crate::{ServerBuilder, test_support};
+
+        struct Summarizer;
+
+        impl ToolsProvider for Summarizer {
+            async fn list_tools(
+                &self,
+                _request: Option<PaginatedRequestParams>,
+                _context: RequestContext<RoleServer>,
+            ) -> Result<ListToolsResult, ErrorData> {
+                Ok(ListToolsResult::default())
+            }
+
+            async fn call_tool(
+                &self,
+                _request: CallToolRequestParams,
+                context: RequestContext<RoleServer>,
+            ) -> Result<CallToolResult, ErrorData> {
+                let reply = Sampling::new(100)
+                    .user("List two colors as JSON.")
+                    .send(&context)
+                    .await?;
+                let colors: Vec<String> = reply.json()?;
+                Ok(CallToolResult::success(vec![Content::text(format!(
+                    "{} {}",
+                    reply.model(),
+                    colors.join(",")
+                ))]))
+            }
+        }
+
+        struct Model;
+
+        impl ClientHandler for Model {
+            async fn create_message(
+                &self,
+                params: CreateMessageRequestParams,
+                _context: RequestContext<RoleClient>,
+            ) -> Result<CreateMessageResult, ErrorData> {
+                assert_eq!(params.max_tokens, 100);
+                Ok(CreateMessageResult {
+                    model: "test-model".into(),
+                    stop_reason: Some(CreateMessageResult::STOP_REASON_END_TURN.into()),
+                    message: SamplingMessage::new(
+                        Role::Assistant,
+                        SamplingMessageContent::text("```json\n[\"red\", \"blue\"]\n```"),
+                    ),
+                })
+            }
+
+            fn get_info(&self) -> ClientInfo {
+                ClientInfo {
+                    capabilities: ClientCapabilities::builder().enable_sampling().build(),
+                    ..Default::default()
+                }
+            }
+        }
+
+        async fn call<C: ClientHandler>(client: C) -> Result<CallToolResult, ServiceError> {
+            let server = ServerBuilder::new()
+                .info(Implementation::default())
+                .tools(Summarizer)
+                .build();
+            let client = test_support::connect(server, client).await;
+            client.call_tool(test_support::call("summarize")).await
+        }
+
+        #[tokio::test]
+        async fn test_sampling() {
+            let result = call(Model).await.unwrap();
+            assert_eq!(
+                result.content[0].as_text().unwrap().text,
+                "test-model red,blue"
+            );
+
+            let Err(ServiceError::McpError(error)) = call(()).await else {
+                panic!("expected the tool call to fail");
+            };
+            assert_eq!(error.code, ErrorCode::INVALID_REQUEST);
+        }
+    }
+}
@@ -0,0 +1,227 @@
+//! Helpers serving a composed server over rmcp's transports.
+//!
+//! With the `stdio` feature, [`Server::serve_stdio`] serves a single session over the
+//! process stdin and stdout. With the `http` feature, [`Server::serve_http`] serves
+//! streamable HTTP sessions on `/mcp`, as does [`ServerFactory::serve_http`] with a
+//! server created per session. Both run in a background task, returned as a
+//! [`Serving`] handle, and shut down gracefully on Ctrl-C, `SIGTERM` or
+//! [`Serving::shutdown`].
+
+use std::future::Future;
+use std::io;
+#[cfg(feature = "http")]
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+#[cfg(feature = "http")]
+use std::sync::Arc;
+
+#[cfg(feature = "stdio")]
+use rmcp::ServiceExt;
+use rmcp::handler::server::ServerHandler;
+#[cfg(feature = "http")]
+use rmcp::{
+    Service,
+    service::RoleServer,
+    transport::streamable_http_server::{
+        SessionManager, StreamableHttpServerConfig, StreamableHttpService,
+        session::local::LocalSessionManager,
+    },
+};
+#[cfg(feature = "http")]
+use tokio::net::{TcpListener, ToSocketAddrs};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+#[cfg(feature = "http")]
+use crate::factory::ServerFactory;
+use crate::server::Server;
+
+/// Path streamable HTTP sessions are served on by [`Server::serve_http`].
+#[cfg(feature = "http")]
+pub const HTTP_PATH: &str = "/mcp";
+
+/// Handle of a server running in a background task.
+///
+/// Awaiting the handle waits for the server to stop. Dropping it leaves the server
+/// running.
+#[derive(Debug)]
+pub struct Serving {
+    handle: JoinHandle<io::Result<()>>,
+    shutdown: CancellationToken,
+    #[cfg(feature = "http")]
+    local_addr: Option<SocketAddr>,
+}
+
+impl Serving {
+    /// Stop the server gracefully, as if a shutdown signal had been received.
+    pub fn shutdown(&self) {
+        self.shutdown.cancel();
+    }
+
+    /// Get the address the HTTP server listens on.
+    #[cfg(feature = "http")]
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    /// Get the handle of the background task.
+    pub fn into_join_handle(self) -> JoinHandle<io::Result<()>> {
+        self.handle
+    }
+}
+
+impl Future for Serving {
+    type Output = io::Result<()>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.handle)
+            .poll(cx)
+            .map(|result| result.map_err(io::Error::other)?)
+    }
+}
+
+/// Cancel `shutdown` on Ctrl-C or `SIGTERM`, unless it is cancelled first.
+async fn cancel_on_signal(shutdown: CancellationToken) {
+    shutdown.run_until_cancelled(signal()).await;
+    shutdown.cancel();
+}
+
+async fn signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let Ok(mut terminate) = signal(SignalKind::terminate()) else {
+            let _ = tokio::signal::ctrl_c().await;
+            return;
+        };
+        let mut ctrl_c = std::pin::pin!(tokio::signal::ctrl_c());
+        std::future::poll_fn(|cx| {
+            if ctrl_c.as_mut().poll(cx).is_ready() || terminate.poll_recv(cx).is_ready() {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await;
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+impl<T, P, R, C, L, I> Server<T, P, R, C, L, I>
+where
+    Self: ServerHandler,
+{
+    /// Serve a single session over stdin and stdout in a background task.
+    ///
+    /// The task ends when the client closes the session or on shutdown.
+    #[cfg(feature = "stdio")]
+    pub fn serve_stdio(self) -> Serving {
+        let shutdown = CancellationToken::new();
+        let token = shutdown.clone();
+        let handle = tokio::spawn(async move {
+            let signal = tokio::spawn(cancel_on_signal(token.clone()));
+            let result = async {
+                let service = self
+                    .serve_with_ct(rmcp::transport::stdio(), token.child_token())
+                    .await
+                    .map_err(io::Error::other)?;
+                service.waiting().await.map_err(io::Error::other)?;
+                Ok(())
+            }
+            .await;
+            signal.abort();
+            result
+        });
+        Serving {
+            handle,
+            shutdown,
+            #[cfg(feature = "http")]
+            local_addr: None,
+        }
+    }
+
+    /// Serve streamable HTTP sessions on [`HTTP_PATH`] in a background task.
+    ///
+    /// Each session gets its own clone of the server, and thus its own
+    /// [`SessionId`](crate::SessionId). Returns once the listener is bound.
+    #[cfg(feature = "http")]
+    pub async fn serve_http(self, addr: impl ToSocketAddrs) -> io::Result<Serving>
+    where
+        Self: Clone,
+    {
+        ServerFactory::new(move || self.clone())
+            .serve_http(addr)
+            .await
+    }
+}
+
+#[cfg(feature = "http")]
+impl<S> ServerFactory<S>
+where
+    S: Service<RoleServer> + Send + 'static,
+{
+    /// Create a streamable HTTP service creating a server for every session.
+    pub fn streamable_http_service<M: SessionManager>(
+        &self,
+        session_manager: Arc<M>,
+        config: StreamableHttpServerConfig,
+    ) -> StreamableHttpService<S, M> {
+        let factory = self.clone();
+        StreamableHttpService::new(move || Ok(factory.create()), session_manager, config)
+    }
+
+    /// Serve streamable HTTP sessions on [`HTTP_PATH`] in a background task.
+    ///
+    /// Every session is served by a server created by the factory. Returns once the
+    /// listener is bound.
+    pub async fn serve_http(self, addr: impl ToSocketAddrs) -> io::Result<Serving> {
+        let listener = TcpListener::bind(addr).await?;
+        let local_addr = listener.local_addr()?;
+        let shutdown = CancellationToken::new();
+        let config = StreamableHttpServerConfig {
+            cancellation_token: shutdown.child_token(),
+            ..Default::default()
+        };
+        let service =
+            self.streamable_http_service(Arc::new(LocalSessionManager::default()), config);
+        let router = axum::Router::new().route_service(HTTP_PATH, service);
+        let token = shutdown.clone();
+        let handle = tokio::spawn(async move {
+            axum::serve(listener, router)
+                .with_graceful_shutdown(cancel_on_signal(token))
+                .await
+        });
+        Ok(Serving {
+            handle,
+            shutdown,
+            local_addr: Some(local_addr),
+        })
+    }
+}
+
+#[cfg(all(test, feature = "http"))]
+mod tests {
+    use rmcp::model::Implementation;
+
+    use crate::ServerBuilder;
+
+    #[tokio::test]
+    async fn test_serve_http_shutdown() {
+        let server = ServerBuilder::new()
+            .info(Implementation::from_build_env())
+            .build();
+        let serving = server.serve_http("127.0.0.1:0").await.unwrap();
+        let addr = serving.local_addr().unwrap();
+        assert!(tokio::net::TcpStream::connect(addr).await.is_ok());
+
+        serving.shutdown();
+        serving.await.unwrap();
+        assert!(tokio::net::TcpStream::connect(addr).await.is_err());
+    }
+}
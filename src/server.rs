@@ -1,5 +1,9 @@
 //! The composed Server type and its ServerHandler implementation.
 
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::task::Poll;
+
 use rmcp::{
     handler::server::ServerHandler,
     model::{
@@ -7,17 +11,30 @@ use rmcp::{
         CompleteResult, ErrorCode, ErrorData, GetPromptRequestParams, GetPromptResult,
         InitializeRequestParams, InitializeResult, JsonObject, ListPromptsResult,
         ListResourceTemplatesResult, ListResourcesResult, ListToolsResult, PaginatedRequestParams,
-        ProgressNotificationParam, PromptsCapability, ReadResourceRequestParams,
+        ProgressNotificationParam, PromptsCapability, ProtocolVersion, ReadResourceRequestParams,
         ReadResourceResult, ResourcesCapability, ServerCapabilities, ServerInfo,
         SetLevelRequestParams, SubscribeRequestParams, ToolsCapability, UnsubscribeRequestParams,
     },
     service::{NotificationContext, RequestContext, RoleServer},
 };
+use serde_json::json;
 
+use crate::alias::AliasChain;
+use crate::auth::Authorizer;
 use crate::providers::{
     CompletionProvider, LoggingProvider, PromptsProvider, ResourcesProvider, ServerInfoProvider,
     ToolsProvider,
 };
+use crate::request::RequestKind;
+use crate::session::{ConnectedClient, SessionId, SessionState};
+#[cfg(any(feature = "tracing", feature = "metrics"))]
+use crate::telemetry;
+
+/// Hook called with the request and panic message when a provider panics.
+pub(crate) type PanicHook = Arc<dyn Fn(&RequestKind, &str) + Send + Sync>;
+
+/// Hook called with the initialize request and the emptied state of the session.
+pub(crate) type SessionInit = Arc<dyn Fn(&InitializeRequestParams, &SessionState) + Send + Sync>;
 
 /// Marker for an unset provider.
 #[derive(Clone, Copy, Debug, Default)]
@@ -35,7 +52,9 @@ pub struct Unset;
 /// - `C`: Completion provider (or `Unset`)
 /// - `L`: Logging provider (or `Unset`)
 /// - `I`: Server info provider (required)
-#[derive(Clone)]
+///
+/// Cloning a server gives the clone a new [`SessionId`] and an empty [`SessionState`],
+/// so a server can be cloned once per connection to serve several sessions.
 pub struct Server<T, P, R, C, L, I> {
     pub(crate) tools: Option<T>,
     pub(crate) prompts: Option<P>,
@@ -44,6 +63,148 @@ pub struct Server<T, P, R, C, L, I> {
     pub(crate) logging: Option<L>,
     pub(crate) info: I,
     pub(crate) instructions: Option<String>,
+    pub(crate) authorizer: Option<Arc<dyn Authorizer>>,
+    pub(crate) panic_hook: Option<PanicHook>,
+    pub(crate) session_init: Option<SessionInit>,
+    pub(crate) protocol_versions: Option<Arc<[ProtocolVersion]>>,
+    pub(crate) tool_aliases: AliasChain,
+    pub(crate) session_id: SessionId,
+    pub(crate) session_state: SessionState,
+}
+
+impl<T, P, R, C, L, I> Clone for Server<T, P, R, C, L, I>
+where
+    T: Clone,
+    P: Clone,
+    R: Clone,
+    C: Clone,
+    L: Clone,
+    I: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            tools: self.tools.clone(),
+            prompts: self.prompts.clone(),
+            resources: self.resources.clone(),
+            completion: self.completion.clone(),
+            logging: self.logging.clone(),
+            info: self.info.clone(),
+            instructions: self.instructions.clone(),
+            authorizer: self.authorizer.clone(),
+            panic_hook: self.panic_hook.clone(),
+            session_init: self.session_init.clone(),
+            protocol_versions: self.protocol_versions.clone(),
+            tool_aliases: self.tool_aliases.clone(),
+            session_id: SessionId::next(),
+            session_state: SessionState::default(),
+        }
+    }
+}
+
+impl<T, P, R, C, L, I> Server<T, P, R, C, L, I> {
+    /// Get the identifier of the session served by this server.
+    pub fn session_id(&self) -> SessionId {
+        self.session_id
+    }
+
+    /// Get the state of the session served by this server.
+    pub fn session_state(&self) -> &SessionState {
+        &self.session_state
+    }
+
+    /// Get the name of a called tool, resolving the aliases of
+    /// [`alias_tools`](crate::ServerBuilder::alias_tools) from the outermost in.
+    fn canonical_tool_name(&self, name: &str) -> String {
+        self.tool_aliases.resolve(name).to_owned()
+    }
+
+    /// Authorize a request and pass it to a provider.
+    ///
+    /// Per-session data is attached to the request context before the provider is called,
+    /// and a panicking provider fails the request with an internal error. With the
+    /// `tracing` feature, the request runs inside an `mcp.request` span; with the
+    /// `metrics` feature, it is counted and timed.
+    async fn dispatch<F, Fut, Res>(
+        &self,
+        kind: RequestKind,
+        context: RequestContext<RoleServer>,
+        call: F,
+    ) -> Result<Res, ErrorData>
+    where
+        F: FnOnce(RequestContext<RoleServer>) -> Fut,
+        Fut: Future<Output = Result<Res, ErrorData>>,
+    {
+        #[cfg(feature = "tracing")]
+        let span = telemetry::request_span(&kind, self.session_id, &context.meta);
+        #[cfg(feature = "metrics")]
+        let started = std::time::Instant::now();
+
+        let result = self.catch_panic(&kind, self.authorize_and_call(&kind, context, call));
+        #[cfg(feature = "tracing")]
+        let result = tracing::Instrument::instrument(result, span.clone());
+        let result = result.await;
+
+        #[cfg(feature = "tracing")]
+        telemetry::record_outcome(&span, &result);
+        #[cfg(feature = "metrics")]
+        telemetry::record_metrics(&kind, &self.session_state, started.elapsed(), &result);
+        result
+    }
+
+    /// Run a request, turning a panic into an internal error so the session survives.
+    async fn catch_panic<Res>(
+        &self,
+        kind: &RequestKind,
+        future: impl Future<Output = Result<Res, ErrorData>>,
+    ) -> Result<Res, ErrorData> {
+        let mut future = std::pin::pin!(future);
+        let outcome = std::future::poll_fn(|cx| {
+            match panic::catch_unwind(AssertUnwindSafe(|| future.as_mut().poll(cx))) {
+                Ok(Poll::Ready(result)) => Poll::Ready(Ok(result)),
+                Ok(Poll::Pending) => Poll::Pending,
+                Err(payload) => Poll::Ready(Err(payload)),
+            }
+        })
+        .await;
+
+        outcome.unwrap_or_else(|payload| {
+            let message = payload
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("unknown panic");
+            if let Some(hook) = &self.panic_hook {
+                hook(kind, message);
+            }
+            Err(ErrorData::internal_error(
+                format!("{kind} failed: provider panicked"),
+                None,
+            ))
+        })
+    }
+
+    async fn authorize_and_call<F, Fut, Res>(
+        &self,
+        kind: &RequestKind,
+        mut context: RequestContext<RoleServer>,
+        call: F,
+    ) -> Result<Res, ErrorData>
+    where
+        F: FnOnce(RequestContext<RoleServer>) -> Fut,
+        Fut: Future<Output = Result<Res, ErrorData>>,
+    {
+        if let Some(authorizer) = &self.authorizer {
+            let identity = authorizer.identity(&context);
+            authorizer.authorize(kind, identity.as_ref())?;
+            if let Some(identity) = identity {
+                context.extensions.insert(identity);
+            }
+        }
+        context.extensions.insert(self.session_id);
+        context.extensions.insert(self.session_state.clone());
+        context.extensions.insert(self.tool_aliases.clone());
+        call(context).await
+    }
 }
 
 impl<T, P, R, C, L, I> Server<T, P, R, C, L, I>
@@ -71,6 +232,31 @@ where
     }
 }
 
+/// Pick the protocol version to use with a client requesting `requested`.
+///
+/// The requested version wins when supported, then the newest supported version older
+/// than it. Clients requesting a version older than every supported one are rejected.
+fn negotiate_protocol_version(
+    supported: &[ProtocolVersion],
+    requested: &ProtocolVersion,
+) -> Result<ProtocolVersion, ErrorData> {
+    supported
+        .iter()
+        .filter(|version| *version <= requested)
+        .max_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+        .cloned()
+        .ok_or_else(|| {
+            let versions: Vec<String> = supported.iter().map(ToString::to_string).collect();
+            ErrorData::invalid_params(
+                format!(
+                    "unsupported protocol version {requested}, supported versions: {}",
+                    versions.join(", ")
+                ),
+                Some(json!({ "supported": versions, "requested": requested })),
+            )
+        })
+}
+
 // =============================================================================
 // ServerHandler implementation
 // =============================================================================
@@ -95,12 +281,24 @@ where
 
     async fn initialize(
         &self,
-        _request: InitializeRequestParams,
-        _context: RequestContext<RoleServer>,
+        request: InitializeRequestParams,
+        context: RequestContext<RoleServer>,
     ) -> Result<InitializeResult, ErrorData> {
         let base = self.info.get_info();
+        let protocol_version = match &self.protocol_versions {
+            Some(supported) => negotiate_protocol_version(supported, &request.protocol_version)?,
+            None => base.protocol_version,
+        };
+        self.session_state.clear();
+        self.session_state.insert(ConnectedClient::from(&request));
+        if context.peer.peer_info().is_none() {
+            context.peer.set_peer_info(request.clone());
+        }
+        if let Some(init) = &self.session_init {
+            init(&request, &self.session_state);
+        }
         Ok(InitializeResult {
-            protocol_version: base.protocol_version,
+            protocol_version,
             capabilities: self.combined_capabilities(),
             server_info: base.server_info,
             instructions: self.instructions.clone().or(base.instructions),
@@ -117,7 +315,20 @@ where
         context: RequestContext<RoleServer>,
     ) -> Result<ListToolsResult, ErrorData> {
         match &self.tools {
-            Some(provider) => provider.list_tools(request, context).await,
+            Some(provider) => {
+                let kind = RequestKind::ListTools;
+                let result = self
+                    .dispatch(kind, context, |context| {
+                        provider.list_tools(request, context)
+                    })
+                    .await;
+                #[cfg(feature = "metrics")]
+                if let Ok(result) = &result {
+                    let names = result.tools.iter().map(|tool| tool.name.as_ref());
+                    telemetry::ListedTools::record(&self.session_state, names);
+                }
+                result
+            }
             None => Err(ErrorData::new(
                 ErrorCode::METHOD_NOT_FOUND,
                 "tools not supported",
@@ -132,7 +343,15 @@ where
         context: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, ErrorData> {
         match &self.tools {
-            Some(provider) => provider.call_tool(request, context).await,
+            Some(provider) => {
+                let kind = RequestKind::CallTool {
+                    name: self.canonical_tool_name(&request.name),
+                };
+                self.dispatch(kind, context, |context| {
+                    provider.call_tool(request, context)
+                })
+                .await
+            }
             None => Err(ErrorData::new(
                 ErrorCode::METHOD_NOT_FOUND,
                 "tools not supported",
@@ -147,7 +366,13 @@ where
         context: RequestContext<RoleServer>,
     ) -> Result<ListPromptsResult, ErrorData> {
         match &self.prompts {
-            Some(provider) => provider.list_prompts(request, context).await,
+            Some(provider) => {
+                let kind = RequestKind::ListPrompts;
+                self.dispatch(kind, context, |context| {
+                    provider.list_prompts(request, context)
+                })
+                .await
+            }
             None => Err(ErrorData::new(
                 ErrorCode::METHOD_NOT_FOUND,
                 "prompts not supported",
@@ -162,7 +387,15 @@ where
         context: RequestContext<RoleServer>,
     ) -> Result<GetPromptResult, ErrorData> {
         match &self.prompts {
-            Some(provider) => provider.get_prompt(request, context).await,
+            Some(provider) => {
+                let kind = RequestKind::GetPrompt {
+                    name: request.name.clone(),
+                };
+                self.dispatch(kind, context, |context| {
+                    provider.get_prompt(request, context)
+                })
+                .await
+            }
             None => Err(ErrorData::new(
                 ErrorCode::METHOD_NOT_FOUND,
                 "prompts not supported",
@@ -177,7 +410,13 @@ where
         context: RequestContext<RoleServer>,
     ) -> Result<ListResourcesResult, ErrorData> {
         match &self.resources {
-            Some(provider) => provider.list_resources(request, context).await,
+            Some(provider) => {
+                let kind = RequestKind::ListResources;
+                self.dispatch(kind, context, |context| {
+                    provider.list_resources(request, context)
+                })
+                .await
+            }
             None => Err(ErrorData::new(
                 ErrorCode::METHOD_NOT_FOUND,
                 "resources not supported",
@@ -192,7 +431,13 @@ where
         context: RequestContext<RoleServer>,
     ) -> Result<ListResourceTemplatesResult, ErrorData> {
         match &self.resources {
-            Some(provider) => provider.list_resource_templates(request, context).await,
+            Some(provider) => {
+                let kind = RequestKind::ListResourceTemplates;
+                self.dispatch(kind, context, |context| {
+                    provider.list_resource_templates(request, context)
+                })
+                .await
+            }
             None => Err(ErrorData::new(
                 ErrorCode::METHOD_NOT_FOUND,
                 "resources not supported",
@@ -207,7 +452,15 @@ where
         context: RequestContext<RoleServer>,
     ) -> Result<ReadResourceResult, ErrorData> {
         match &self.resources {
-            Some(provider) => provider.read_resource(request, context).await,
+            Some(provider) => {
+                let kind = RequestKind::ReadResource {
+                    uri: request.uri.clone(),
+                };
+                self.dispatch(kind, context, |context| {
+                    provider.read_resource(request, context)
+                })
+                .await
+            }
             None => Err(ErrorData::new(
                 ErrorCode::METHOD_NOT_FOUND,
                 "resources not supported",
@@ -222,7 +475,15 @@ where
         context: RequestContext<RoleServer>,
     ) -> Result<(), ErrorData> {
         match &self.resources {
-            Some(provider) => provider.subscribe(request, context).await,
+            Some(provider) => {
+                let kind = RequestKind::Subscribe {
+                    uri: request.uri.clone(),
+                };
+                self.dispatch(kind, context, |context| {
+                    provider.subscribe(request, context)
+                })
+                .await
+            }
             None => Err(ErrorData::new(
                 ErrorCode::METHOD_NOT_FOUND,
                 "resources not supported",
@@ -237,7 +498,15 @@ where
         context: RequestContext<RoleServer>,
     ) -> Result<(), ErrorData> {
         match &self.resources {
-            Some(provider) => provider.unsubscribe(request, context).await,
+            Some(provider) => {
+                let kind = RequestKind::Unsubscribe {
+                    uri: request.uri.clone(),
+                };
+                self.dispatch(kind, context, |context| {
+                    provider.unsubscribe(request, context)
+                })
+                .await
+            }
             None => Err(ErrorData::new(
                 ErrorCode::METHOD_NOT_FOUND,
                 "resources not supported",
@@ -252,7 +521,11 @@ where
         context: RequestContext<RoleServer>,
     ) -> Result<CompleteResult, ErrorData> {
         match &self.completion {
-            Some(provider) => provider.complete(request, context).await,
+            Some(provider) => {
+                let kind = RequestKind::Complete;
+                self.dispatch(kind, context, |context| provider.complete(request, context))
+                    .await
+            }
             None => Err(ErrorData::new(
                 ErrorCode::METHOD_NOT_FOUND,
                 "completion not supported",
@@ -267,7 +540,13 @@ where
         context: RequestContext<RoleServer>,
     ) -> Result<(), ErrorData> {
         match &self.logging {
-            Some(provider) => provider.set_level(request, context).await,
+            Some(provider) => {
+                let kind = RequestKind::SetLevel;
+                self.dispatch(kind, context, |context| {
+                    provider.set_level(request, context)
+                })
+                .await
+            }
             None => Err(ErrorData::new(
                 ErrorCode::METHOD_NOT_FOUND,
                 "logging not supported",
@@ -440,3 +719,175 @@ impl LoggingProvider for Unset {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use rmcp::model::{ErrorCode, Implementation};
+
+    use super::*;
+    use crate::builder::ServerBuilder;
+
+    #[tokio::test]
+    async fn test_catch_panic() {
+        let reported = Arc::new(Mutex::new(None));
+        let hook_reported = reported.clone();
+        let server = ServerBuilder::new()
+            .info(Implementation::default())
+            .panic_hook(move |kind, message| {
+                *hook_reported.lock().unwrap() = Some((kind.clone(), message.to_owned()));
+            })
+            .build();
+        let kind = RequestKind::CallTool {
+            name: "boom".into(),
+        };
+
+        let result: Result<(), ErrorData> = server
+            .catch_panic(&kind, async { panic!("provider bug") })
+            .await;
+        assert_eq!(result.unwrap_err().code, ErrorCode::INTERNAL_ERROR);
+        assert_eq!(
+            reported.lock().unwrap().take(),
+            Some((kind.clone(), "provider bug".to_owned()))
+        );
+
+        assert!(server.catch_panic(&kind, async { Ok(()) }).await.is_ok());
+    }
+
+    #[test]
+    fn test_negotiate_protocol_version() {
+        let supported = [ProtocolVersion::V_2024_11_05, ProtocolVersion::V_2025_03_26];
+        let negotiate = |requested| negotiate_protocol_version(&supported, &requested);
+
+        assert_eq!(
+            negotiate(ProtocolVersion::V_2025_03_26).unwrap(),
+            ProtocolVersion::V_2025_03_26
+        );
+        assert_eq!(
+            negotiate(ProtocolVersion::V_2025_06_18).unwrap(),
+            ProtocolVersion::V_2025_03_26
+        );
+        assert_eq!(
+            negotiate(ProtocolVersion::V_2024_11_05).unwrap(),
+            ProtocolVersion::V_2024_11_05
+        );
+
+        let error = negotiate_protocol_version(
+            &[ProtocolVersion::V_2025_06_18],
+            &ProtocolVersion::V_2025_03_26,
+        )
+        .unwrap_err();
+        assert_eq!(error.code, ErrorCode::INVALID_PARAMS);
+        assert_eq!(error.data.unwrap()["supported"][0], "2025-06-18");
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one protocol version")]
+    fn test_rejects_empty_protocol_versions() {
+        let _ = ServerBuilder::new().protocol_versions([]);
+    }
+
+    mod session {
+        use rmcp::model::{Content, Implementation};
+
+        use super::*;
+        use crate::auth::{ACCESS_DENIED, Match, Policy, Require};
+        use crate::test_support::{self, call};
+        use crate::{CircuitBreaker, Quota, RATE_LIMITED, Rate, RateLimits, ToolAliases};
+
+        struct Greeter;
+
+        impl ToolsProvider for Greeter {
+            async fn list_tools(
+                &self,
+                _request: Option<PaginatedRequestParams>,
+                _context: RequestContext<RoleServer>,
+            ) -> Result<ListToolsResult, ErrorData> {
+                Ok(ListToolsResult::default())
+            }
+
+            async fn call_tool(
+                &self,
+                _request: CallToolRequestParams,
+                context: RequestContext<RoleServer>,
+            ) -> Result<CallToolResult, ErrorData> {
+                let state = SessionState::from_context(&context).unwrap();
+                let calls = state.get_or_insert_with(|| 0u32) + 1;
+                state.insert(calls);
+                let client = state.get::<String>().unwrap_or_default();
+                Ok(CallToolResult::success(vec![Content::text(format!(
+                    "{client} {calls}"
+                ))]))
+            }
+        }
+
+        #[tokio::test]
+        async fn test_session_state() {
+            let server = ServerBuilder::new()
+                .info(Implementation::default())
+                .tools(Greeter)
+                .init_session(|request, state| {
+                    state.insert(request.client_info.name.clone());
+                })
+                .build();
+            let state = server.session_state().clone();
+            let client = test_support::connect(server, ()).await;
+
+            client.call_tool(call("greet")).await.unwrap();
+            let result = client.call_tool(call("greet")).await.unwrap();
+            let client_name = Implementation::from_build_env().name;
+            assert_eq!(
+                result.content[0].as_text().unwrap().text,
+                format!("{client_name} 2")
+            );
+            assert_eq!(state.get::<u32>(), Some(2));
+            let connected = state.get::<ConnectedClient>().unwrap();
+            assert_eq!(connected.info().name, client_name);
+            assert!(!connected.supports_sampling());
+        }
+
+        #[tokio::test]
+        async fn test_authorizes_aliases_as_their_target() {
+            let server = ServerBuilder::new()
+                .info(Implementation::default())
+                .tools(Greeter)
+                .alias_tools(ToolAliases::new().alias("remove_branch", "delete_branch"))
+                .authorizer(Policy::new().rule(Match::tool("delete_*"), Require::role("admin")))
+                .build();
+            assert_eq!(server.canonical_tool_name("remove_branch"), "delete_branch");
+            assert_eq!(server.canonical_tool_name("greet"), "greet");
+            let client = test_support::connect(server, ()).await;
+
+            let error = client.call_tool(call("remove_branch")).await.unwrap_err();
+            assert!(matches!(
+                error,
+                rmcp::service::ServiceError::McpError(ErrorData { code, .. }) if code == ACCESS_DENIED
+            ));
+        }
+
+        #[tokio::test]
+        async fn test_limits_aliases_as_their_target() {
+            let breaker = CircuitBreaker::new();
+            let server = ServerBuilder::new()
+                .info(Implementation::default())
+                .tools(Greeter)
+                .alias_tools(ToolAliases::new().alias("remove_branch", "delete_branch"))
+                .guard_tools(breaker.clone())
+                .rate_limit_tools(
+                    RateLimits::new().tool("delete_branch", Quota::new().rate(Rate::per_minute(1))),
+                )
+                .build();
+            let client = test_support::connect(server, ()).await;
+
+            client.call_tool(call("remove_branch")).await.unwrap();
+            assert!(breaker.status("delete_branch").is_some());
+            assert!(breaker.status("remove_branch").is_none());
+            let error = client.call_tool(call("delete_branch")).await.unwrap_err();
+            assert!(matches!(
+                error,
+                rmcp::service::ServiceError::McpError(ErrorData { code, .. }) if code == RATE_LIMITED
+            ));
+        }
+    }
+}
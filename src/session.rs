@@ -0,0 +1,307 @@
+//! Session identity, state and client for composed servers.
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use rmcp::model::{ClientCapabilities, Implementation, InitializeRequestParams, ProtocolVersion};
+use rmcp::service::{RequestContext, RoleServer};
+
+/// Identifier of a single MCP session served by a composed [`Server`](crate::Server).
+///
+/// Every `Server` value gets a fresh identifier, including clones, so a server that is
+/// cloned once per connection yields one identifier per session. The server stores its
+/// identifier in the request extensions before calling a provider, which lets shared
+/// providers keep per-session data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SessionId(u64);
+
+impl SessionId {
+    /// Allocate a new, process-unique session identifier.
+    pub(crate) fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(1);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Get the session identifier of a request dispatched by a composed server.
+    ///
+    /// Returns `None` when the provider is called outside of a composed server.
+    pub fn from_context(context: &RequestContext<RoleServer>) -> Option<Self> {
+        context.extensions.get::<Self>().copied()
+    }
+
+    /// Get the raw numeric value of this identifier.
+    pub fn as_u64(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Typed values kept for the duration of a session.
+///
+/// Every composed [`Server`](crate::Server) owns one state, emptied when a client
+/// initializes the session and dropped with the server when the session ends. The
+/// server stores a handle to it in the request extensions before calling a provider,
+/// so shared providers can keep session-specific data such as a chosen workspace.
+/// The state holds at most one value per type.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::path::PathBuf;
+/// use rmcp::service::{RequestContext, RoleServer};
+/// use rmcp_server_builder::SessionState;
+///
+/// #[derive(Clone)]
+/// struct Workspace(PathBuf);
+///
+/// # fn open(path: PathBuf, context: RequestContext<RoleServer>) {
+/// // In a provider method:
+/// let state = SessionState::from_context(&context).expect("called by a composed server");
+/// state.insert(Workspace(path));
+/// let workspace = state.get::<Workspace>();
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct SessionState {
+    values: Arc<Mutex<HashMap<TypeId, Box<dyn Any + Send + Sync>>>>,
+}
+
+impl SessionState {
+    /// Get the state of the session of a request dispatched by a composed server.
+    ///
+    /// Returns `None` when the provider is called outside of a composed server.
+    pub fn from_context(context: &RequestContext<RoleServer>) -> Option<Self> {
+        context.extensions.get::<Self>().cloned()
+    }
+
+    /// Get a copy of the value of type `T`.
+    pub fn get<T: Clone + Send + Sync + 'static>(&self) -> Option<T> {
+        let values = self.values.lock().unwrap();
+        values.get(&TypeId::of::<T>())?.downcast_ref::<T>().cloned()
+    }
+
+    /// Check whether a value of type `T` is stored.
+    pub fn contains<T: Send + Sync + 'static>(&self) -> bool {
+        self.values.lock().unwrap().contains_key(&TypeId::of::<T>())
+    }
+
+    /// Store a value, returning the previous value of the same type.
+    pub fn insert<T: Send + Sync + 'static>(&self, value: T) -> Option<T> {
+        let mut values = self.values.lock().unwrap();
+        let previous = values.insert(TypeId::of::<T>(), Box::new(value))?;
+        previous.downcast().ok().map(|previous| *previous)
+    }
+
+    /// Remove and return the value of type `T`.
+    pub fn remove<T: Send + Sync + 'static>(&self) -> Option<T> {
+        let mut values = self.values.lock().unwrap();
+        let value = values.remove(&TypeId::of::<T>())?;
+        value.downcast().ok().map(|value| *value)
+    }
+
+    /// Get a copy of the value of type `T`, storing the result of `init` first if there
+    /// is none.
+    pub fn get_or_insert_with<T: Clone + Send + Sync + 'static>(
+        &self,
+        init: impl FnOnce() -> T,
+    ) -> T {
+        let mut values = self.values.lock().unwrap();
+        let value = values
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(init()));
+        value.downcast_ref::<T>().cloned().expect("value of type T")
+    }
+
+    /// Modify the value of type `T` in place, returning the result of `update`.
+    ///
+    /// Returns `None` when no value of type `T` is stored. The state is locked while
+    /// `update` runs, so it must not access the state itself.
+    pub fn update<T: Send + Sync + 'static, U>(
+        &self,
+        update: impl FnOnce(&mut T) -> U,
+    ) -> Option<U> {
+        let mut values = self.values.lock().unwrap();
+        let value = values.get_mut(&TypeId::of::<T>())?.downcast_mut::<T>()?;
+        Some(update(value))
+    }
+
+    /// Modify the value of type `T` in place, storing its default value first if there
+    /// is none.
+    pub(crate) fn update_or_default<T: Default + Send + Sync + 'static, U>(
+        &self,
+        update: impl FnOnce(&mut T) -> U,
+    ) -> U {
+        let mut values = self.values.lock().unwrap();
+        let value = values
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::<T>::default());
+        update(value.downcast_mut::<T>().expect("value of type T"))
+    }
+
+    /// Remove all values.
+    pub fn clear(&self) {
+        self.values.lock().unwrap().clear();
+    }
+}
+
+impl fmt::Debug for SessionState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionState")
+            .field("len", &self.values.lock().unwrap().len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Client of a session, as announced when it initialized the session.
+///
+/// A composed [`Server`](crate::Server) records the client in its [`SessionState`]
+/// on initialization, so providers can check what the client supports before sending
+/// it sampling, elicitation or roots requests.
+///
+/// # Example
+///
+/// ```no_run
+/// use rmcp::model::ErrorData;
+/// use rmcp::service::{RequestContext, RoleServer};
+/// use rmcp_server_builder::ConnectedClient;
+///
+/// # fn check(context: RequestContext<RoleServer>) -> Result<(), ErrorData> {
+/// // In a provider method:
+/// let client = ConnectedClient::from_context(&context);
+/// if !client.is_some_and(|client| client.supports_sampling()) {
+///     return Err(ErrorData::invalid_request("the client does not support sampling", None));
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConnectedClient {
+    protocol_version: ProtocolVersion,
+    capabilities: ClientCapabilities,
+    info: Implementation,
+}
+
+impl ConnectedClient {
+    /// Get the client of the session of a request.
+    ///
+    /// Falls back to the client rmcp recorded on the peer when the provider is called
+    /// outside of a composed server. Returns `None` before the session is initialized.
+    pub fn from_context(context: &RequestContext<RoleServer>) -> Option<Self> {
+        SessionState::from_context(context)
+            .and_then(|state| state.get::<Self>())
+            .or_else(|| context.peer.peer_info().map(Self::from))
+    }
+
+    /// Get the protocol version requested by the client.
+    pub fn protocol_version(&self) -> &ProtocolVersion {
+        &self.protocol_version
+    }
+
+    /// Get the capabilities announced by the client.
+    pub fn capabilities(&self) -> &ClientCapabilities {
+        &self.capabilities
+    }
+
+    /// Get the name and version of the client implementation.
+    pub fn info(&self) -> &Implementation {
+        &self.info
+    }
+
+    /// Check whether the client accepts sampling requests.
+    pub fn supports_sampling(&self) -> bool {
+        self.capabilities.sampling.is_some()
+    }
+
+    /// Check whether the client accepts tools in sampling requests.
+    pub fn supports_sampling_tools(&self) -> bool {
+        self.capabilities
+            .sampling
+            .as_ref()
+            .is_some_and(|sampling| sampling.tools.is_some())
+    }
+
+    /// Check whether the client accepts elicitation requests.
+    pub fn supports_elicitation(&self) -> bool {
+        self.capabilities.elicitation.is_some()
+    }
+
+    /// Check whether the client lists its roots.
+    pub fn supports_roots(&self) -> bool {
+        self.capabilities.roots.is_some()
+    }
+
+    /// Check whether the client notifies changes of its roots.
+    pub fn supports_roots_list_changed(&self) -> bool {
+        self.capabilities
+            .roots
+            .as_ref()
+            .is_some_and(|roots| roots.list_changed == Some(true))
+    }
+}
+
+impl From<&InitializeRequestParams> for ConnectedClient {
+    fn from(request: &InitializeRequestParams) -> Self {
+        Self {
+            protocol_version: request.protocol_version.clone(),
+            capabilities: request.capabilities.clone(),
+            info: request.client_info.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Workspace(&'static str);
+
+    #[test]
+    fn test_session_state() {
+        let state = SessionState::default();
+        assert_eq!(state.get::<Workspace>(), None);
+        assert_eq!(state.insert(Workspace("a")), None);
+        assert_eq!(state.insert(Workspace("b")), Some(Workspace("a")));
+        assert_eq!(state.get_or_insert_with(|| Workspace("c")), Workspace("b"));
+        assert_eq!(state.get_or_insert_with(|| 1u32), 1);
+
+        assert_eq!(
+            state.update(|count: &mut u32| {
+                *count += 1;
+                *count
+            }),
+            Some(2)
+        );
+        assert_eq!(state.update(|_: &mut String| ()), None);
+
+        // Handles share the same values.
+        let handle = state.clone();
+        assert_eq!(handle.remove::<Workspace>(), Some(Workspace("b")));
+        assert!(!state.contains::<Workspace>());
+        state.clear();
+        assert_eq!(handle.get::<u32>(), None);
+    }
+
+    #[test]
+    fn test_connected_client() {
+        let mut request = InitializeRequestParams::default();
+        let client = ConnectedClient::from(&request);
+        assert!(!client.supports_sampling());
+        assert!(!client.supports_elicitation());
+        assert!(!client.supports_roots());
+
+        request.capabilities = ClientCapabilities::builder()
+            .enable_sampling()
+            .enable_roots()
+            .enable_roots_list_changed()
+            .build();
+        let client = ConnectedClient::from(&request);
+        assert!(client.supports_sampling());
+        assert!(!client.supports_sampling_tools());
+        assert!(!client.supports_elicitation());
+        assert!(client.supports_roots_list_changed());
+        assert_eq!(client.info(), &request.client_info);
+    }
+}
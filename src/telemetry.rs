@@ -0,0 +1,209 @@
+//! Tracing spans and metrics for dispatched requests.
+//!
+//! With the `tracing` feature, the composed server runs every request dispatched to a
+//! provider inside an `mcp.request` span. With the `opentelemetry` feature, the span is
+//! parented to the trace context found in the request `_meta` (`traceparent` and
+//! `tracestate` keys), using the globally configured text map propagator.
+//!
+//! With the `metrics` feature, every request is counted and timed through the
+//! [`metrics`](::metrics) facade. Tool calls are labelled with the called tool only
+//! when it was listed to the session, so made-up names cannot grow the label set.
+
+#[cfg(feature = "metrics")]
+use std::collections::HashSet;
+#[cfg(feature = "metrics")]
+use std::time::Duration;
+
+use rmcp::model::ErrorData;
+#[cfg(feature = "tracing")]
+use rmcp::model::Meta;
+#[cfg(feature = "tracing")]
+use tracing::{Span, field::Empty};
+
+use crate::request::RequestKind;
+#[cfg(feature = "tracing")]
+use crate::session::SessionId;
+#[cfg(feature = "metrics")]
+use crate::session::SessionState;
+
+/// Counter of dispatched requests, labelled by `method` and `tool`.
+#[cfg(feature = "metrics")]
+pub(crate) const REQUESTS_TOTAL: &str = "mcp_requests_total";
+
+/// Counter of failed requests, labelled by `method`, `tool` and `code`.
+#[cfg(feature = "metrics")]
+pub(crate) const REQUEST_ERRORS_TOTAL: &str = "mcp_request_errors_total";
+
+/// Histogram of request durations in seconds, labelled by `method` and `tool`.
+#[cfg(feature = "metrics")]
+pub(crate) const REQUEST_DURATION_SECONDS: &str = "mcp_request_duration_seconds";
+
+/// `tool` label of calls to tools that were not listed to the session.
+#[cfg(feature = "metrics")]
+pub(crate) const UNKNOWN_TOOL: &str = "unknown";
+
+/// Names of the tools listed to a session, kept in its [`SessionState`].
+#[cfg(feature = "metrics")]
+#[derive(Default)]
+pub(crate) struct ListedTools(HashSet<String>);
+
+#[cfg(feature = "metrics")]
+impl ListedTools {
+    /// Remember the names of tools listed to the session of `state`.
+    pub(crate) fn record<'a>(state: &SessionState, names: impl IntoIterator<Item = &'a str>) {
+        state.update_or_default(|listed: &mut Self| {
+            listed.0.extend(names.into_iter().map(str::to_owned));
+        });
+    }
+
+    /// Get the `tool` label of a request: the called tool if it was listed to the
+    /// session, [`UNKNOWN_TOOL`] if not, and empty for other requests.
+    fn label(state: &SessionState, kind: &RequestKind) -> String {
+        let Some(name) = kind.tool_name() else {
+            return String::new();
+        };
+        let listed = state
+            .update(|listed: &mut Self| listed.0.contains(name))
+            .unwrap_or(false);
+        if listed { name } else { UNKNOWN_TOOL }.to_owned()
+    }
+}
+
+/// Create the span of a dispatched request.
+#[cfg(feature = "tracing")]
+pub(crate) fn request_span(kind: &RequestKind, session: SessionId, meta: &Meta) -> Span {
+    let span = tracing::info_span!(
+        "mcp.request",
+        otel.name = %span_name(kind),
+        otel.kind = "server",
+        otel.status_code = Empty,
+        mcp.method.name = kind.method(),
+        mcp.session.id = session.as_u64(),
+        gen_ai.tool.name = kind.tool_name(),
+        gen_ai.prompt.name = kind.prompt_name(),
+        mcp.resource.uri = kind.resource_uri(),
+        mcp.outcome = Empty,
+        rpc.jsonrpc.error_code = Empty,
+        error.message = Empty,
+    );
+    #[cfg(feature = "opentelemetry")]
+    propagation::set_parent(&span, meta);
+    #[cfg(not(feature = "opentelemetry"))]
+    let _ = meta;
+    span
+}
+
+/// Record the outcome of a request on its span.
+#[cfg(feature = "tracing")]
+pub(crate) fn record_outcome<T>(span: &Span, result: &Result<T, ErrorData>) {
+    match result {
+        Ok(_) => {
+            span.record("mcp.outcome", "ok");
+        }
+        Err(error) => {
+            span.record("mcp.outcome", "error");
+            span.record("otel.status_code", "ERROR");
+            span.record("rpc.jsonrpc.error_code", error.code.0);
+            span.record("error.message", error.message.as_ref());
+        }
+    }
+}
+
+/// Span name following the `{method} {target}` convention.
+#[cfg(feature = "tracing")]
+fn span_name(kind: &RequestKind) -> String {
+    match kind
+        .tool_name()
+        .or(kind.prompt_name())
+        .or(kind.resource_uri())
+    {
+        Some(target) => format!("{} {target}", kind.method()),
+        None => kind.method().to_owned(),
+    }
+}
+
+/// Count and time a dispatched request.
+#[cfg(feature = "metrics")]
+pub(crate) fn record_metrics<T>(
+    kind: &RequestKind,
+    state: &SessionState,
+    elapsed: Duration,
+    result: &Result<T, ErrorData>,
+) {
+    let method = kind.method();
+    let tool = ListedTools::label(state, kind);
+
+    ::metrics::counter!(REQUESTS_TOTAL, "method" => method, "tool" => tool.clone()).increment(1);
+    ::metrics::histogram!(REQUEST_DURATION_SECONDS, "method" => method, "tool" => tool.clone())
+        .record(elapsed.as_secs_f64());
+    if let Err(error) = result {
+        ::metrics::counter!(
+            REQUEST_ERRORS_TOTAL,
+            "method" => method,
+            "tool" => tool,
+            "code" => error.code.0.to_string(),
+        )
+        .increment(1);
+    }
+}
+
+#[cfg(feature = "opentelemetry")]
+mod propagation {
+    use opentelemetry::propagation::Extractor;
+    use rmcp::model::Meta;
+    use tracing::Span;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    /// Reads propagation headers from string entries of `_meta`.
+    struct MetaExtractor<'a>(&'a Meta);
+
+    impl Extractor for MetaExtractor<'_> {
+        fn get(&self, key: &str) -> Option<&str> {
+            self.0.get(key).and_then(|value| value.as_str())
+        }
+
+        fn keys(&self) -> Vec<&str> {
+            self.0.keys().map(String::as_str).collect()
+        }
+    }
+
+    pub(super) fn set_parent(span: &Span, meta: &Meta) {
+        let extractor = MetaExtractor(meta);
+        if extractor.keys().is_empty() {
+            return;
+        }
+        let parent = opentelemetry::global::get_text_map_propagator(|propagator| {
+            propagator.extract(&extractor)
+        });
+        // Fails only when no OpenTelemetry layer is installed.
+        let _ = span.set_parent(parent);
+    }
+}
+
+#[cfg(all(test, any(feature = "tracing", feature = "metrics")))]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_span_name() {
+        let call = RequestKind::CallTool {
+            name: "search".into(),
+        };
+        assert_eq!(span_name(&call), "tools/call search");
+        assert_eq!(span_name(&RequestKind::ListTools), "tools/list");
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_tool_label_requires_listing() {
+        let state = SessionState::default();
+        let call = |name: &str| RequestKind::CallTool { name: name.into() };
+
+        assert_eq!(ListedTools::label(&state, &call("search")), UNKNOWN_TOOL);
+        ListedTools::record(&state, ["search"]);
+        assert_eq!(ListedTools::label(&state, &call("search")), "search");
+        assert_eq!(ListedTools::label(&state, &call("made_up")), UNKNOWN_TOOL);
+        assert_eq!(ListedTools::label(&state, &RequestKind::ListTools), "");
+    }
+}
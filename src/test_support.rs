@@ -0,0 +1,35 @@
+//! Fixtures for tests talking to servers over in-memory transports.
+
+use rmcp::handler::client::ClientHandler;
+use rmcp::model::CallToolRequestParams;
+use rmcp::service::{RoleClient, RunningService};
+use rmcp::{ServerHandler, ServiceExt};
+use tokio::io::DuplexStream;
+
+/// Serve `server` in the background and return the client end of its transport.
+pub(crate) fn serve(server: impl ServerHandler) -> DuplexStream {
+    let (server_io, client_io) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        let service = server.serve(server_io).await.unwrap();
+        service.waiting().await
+    });
+    client_io
+}
+
+/// Serve `server` in the background and connect `client` to it.
+pub(crate) async fn connect<C: ClientHandler>(
+    server: impl ServerHandler,
+    client: C,
+) -> RunningService<RoleClient, C> {
+    client.serve(serve(server)).await.unwrap()
+}
+
+/// A `tools/call` request for `name` without arguments.
+pub(crate) fn call(name: &str) -> CallToolRequestParams {
+    CallToolRequestParams {
+        meta: None,
+        name: name.to_owned().into(),
+        arguments: None,
+        task: None,
+    }
+}